@@ -35,9 +35,19 @@ git_repo_name() {
     return "$exit_code"
 }
 
+_git_repo_name_complete() {
+    local IFS=$'\n'
+    COMPREPLY=($(command git-repo-name-bin __complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+
 if (return 0 2>/dev/null); then
     # Being sourced: define the alias.
     alias git-repo-name=git_repo_name
+
+    # Register dynamic completions (subcommands, config keys, remote names) in bash.
+    if [ -n "$BASH_VERSION" ]; then
+        complete -F _git_repo_name_complete git-repo-name
+    fi
 else
     # Being executed: run the function.
     git_repo_name "$@"
@@ -0,0 +1,90 @@
+use crate::{config, git::redact_credentials, types::Result, utils::fs::set_secure_permissions};
+use log::warn;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One line of the `audit-log` file.
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    /// Seconds since the Unix epoch.
+    timestamp: u64,
+    repo: String,
+    operation: &'a str,
+    old: &'a str,
+    new: &'a str,
+}
+
+/// Appends a change to the configured `audit-log` file, if any. Logging never
+/// fails the change itself: errors are only warned about.
+pub fn record(repo_path: &Path, operation: &str, old: &str, new: &str) {
+    let Some(log_path) = config::current().get_audit_log() else {
+        return;
+    };
+
+    let entry = AuditEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        repo: crate::utils::fs::display_path(repo_path),
+        operation,
+        old: &redact_credentials(old),
+        new: &redact_credentials(new),
+    };
+    if let Err(e) = append(&log_path, &entry) {
+        warn!("Failed to write audit log {}: {}", log_path.display(), e);
+    }
+}
+
+fn append(log_path: &Path, entry: &AuditEntry) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)?;
+    set_secure_permissions(log_path)?;
+
+    let line = serde_json::to_string(entry).map_err(|e| crate::types::Error::Other(e.into()))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{self, Config};
+    use crate::utils::fs::move_directory;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn test_rename_is_audited() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let source = temp.child("old-name");
+        source.create_dir_all()?;
+        let log_path = temp.path().join("audit.log");
+
+        // A scoped config keeps renames in other tests out of this log
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_audit_log(&log_path.display().to_string())?;
+        let new_path = config::with_config(&config, || move_directory(source.path(), "new-name"))?;
+
+        let log = std::fs::read_to_string(&log_path)?;
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", log);
+        let entry: serde_json::Value = serde_json::from_str(lines[0])?;
+        assert_eq!(entry["operation"], "rename-directory");
+        assert!(entry["timestamp"].as_u64().is_some_and(|t| t > 0));
+        assert_eq!(entry["repo"], new_path.display().to_string());
+        assert!(entry["old"].as_str().unwrap().ends_with("old-name"));
+        assert!(entry["new"].as_str().unwrap().ends_with("new-name"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&log_path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,577 @@
+use crate::{
+    config, git,
+    progress::{ProgressSink, SyncOutcome},
+    pull_repo,
+    types::{Error, PullOptions, Result},
+    utils,
+};
+use git2::Repository;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many repositories `pull --recursive` processes at once by default.
+pub const DEFAULT_JOBS: u16 = 4;
+
+/// How many directory levels below the start path `pull --recursive` looks for
+/// repositories by default.
+pub const DEFAULT_DEPTH: u16 = 1;
+
+/// How long a cached repository is skipped before its remote is checked again.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What `pull --recursive --cache` last saw for a repository that was in sync.
+/// The repository's path is the key, so its directory name needs no field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    remote_url: String,
+    /// Seconds since the Unix epoch.
+    checked_at: u64,
+}
+
+/// Repositories that `pull --recursive --cache` found in sync, by canonical path.
+/// A repository whose directory name and remote URL haven't changed since it was
+/// cached is skipped without contacting the remote.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PullCache {
+    entries: BTreeMap<PathBuf, CacheEntry>,
+}
+
+impl PullCache {
+    /// Reads the cache from `path`. A missing or unreadable cache is treated as empty.
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable cache {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Writes the cache over `path` through [`utils::fs::write_atomically`], so an
+    /// interrupted run never leaves a truncated cache behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| Error::Other(e.into()))?;
+        utils::fs::write_atomically(path, |file| file.write_all(contents.as_bytes()))?;
+        Ok(())
+    }
+
+    fn is_fresh(&self, path: &Path, remote_url: &str, now: u64) -> bool {
+        self.entries.get(path).is_some_and(|entry| {
+            entry.remote_url == remote_url
+                && now.saturating_sub(entry.checked_at) < CACHE_TTL.as_secs()
+        })
+    }
+
+    fn record(&mut self, path: PathBuf, remote_url: String, now: u64) {
+        self.entries.insert(
+            path,
+            CacheEntry {
+                remote_url,
+                checked_at: now,
+            },
+        );
+    }
+}
+
+/// Tally of a batch run over several repositories.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub changed: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+impl BatchSummary {
+    /// One-line tally, e.g. "1 changed, 2 unchanged, 0 failed".
+    pub fn describe(&self, dry_run: bool) -> String {
+        format!(
+            "{} {}, {} unchanged, {} failed",
+            self.changed,
+            if dry_run { "out of sync" } else { "changed" },
+            self.unchanged,
+            self.failed
+        )
+    }
+
+    fn count(&mut self, result: &Result<bool>) {
+        match result {
+            Ok(true) => self.changed += 1,
+            Ok(false) => self.unchanged += 1,
+            Err(_) => self.failed += 1,
+        }
+    }
+}
+
+/// Collects a repository's changes on a worker thread, to be reported in order
+/// once it's that repository's turn.
+#[derive(Default)]
+struct BufferedSink {
+    changes: RefCell<Vec<SyncOutcome>>,
+}
+
+impl ProgressSink for BufferedSink {
+    fn on_repo_start(&self, _path: &Path) {}
+
+    fn on_change(&self, outcome: &SyncOutcome) {
+        self.changes.borrow_mut().push(outcome.clone());
+    }
+
+    fn on_repo_done(&self, _path: &Path, _result: &Result<bool>) {}
+}
+
+/// Passes events through to another sink, noting where the directory was renamed to.
+struct RenameTracker<'a> {
+    inner: &'a dyn ProgressSink,
+    renamed_to: RefCell<Option<PathBuf>>,
+}
+
+impl ProgressSink for RenameTracker<'_> {
+    fn on_repo_start(&self, path: &Path) {
+        self.inner.on_repo_start(path);
+    }
+
+    fn on_change(&self, outcome: &SyncOutcome) {
+        if let SyncOutcome::Directory {
+            to, dry_run: false, ..
+        } = outcome
+        {
+            *self.renamed_to.borrow_mut() = Some(to.clone());
+        }
+        self.inner.on_change(outcome);
+    }
+
+    fn on_repo_done(&self, path: &Path, result: &Result<bool>) {
+        self.inner.on_repo_done(path, result);
+    }
+}
+
+/// Runs `pull` in every git repository up to `depth` directory levels below
+/// `root`, reporting progress to `sink`. A failure in one repository is reported
+/// and counted, and the walk carries on with the next.
+///
+/// Up to `jobs` repositories are processed at once. Their progress is still
+/// reported one repository at a time, in path order.
+///
+/// With a `cache`, repositories it has recorded as in sync are skipped unless
+/// `options.force` is set, and repositories found in sync are recorded.
+pub fn pull_recursive(
+    root: &Path,
+    options: &PullOptions,
+    jobs: usize,
+    depth: usize,
+    cache: Option<&mut PullCache>,
+    sink: &dyn ProgressSink,
+) -> Result<BatchSummary> {
+    let mut repos = Vec::new();
+    find_repos(root, depth, &mut repos)?;
+    repos.sort();
+    let mut summary = BatchSummary::default();
+    let cache = cache.map(Mutex::new);
+    let cache = cache.as_ref();
+
+    if jobs <= 1 {
+        for path in &repos {
+            sink.on_repo_start(path);
+            let result = pull_path(path, options, cache, sink);
+            sink.on_repo_done(path, &result);
+            summary.count(&result);
+        }
+        return Ok(summary);
+    }
+
+    // Workers use the same config as the caller, which may not be the global one
+    let config = config::current();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(repos.len()) {
+            let (sender, next, repos, config) = (sender.clone(), &next, &repos, &config);
+            scope.spawn(move || {
+                config::with_config(config, || {
+                    while let Some(path) = repos.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let buffer = BufferedSink::default();
+                        let result = pull_path(path, options, cache, &buffer);
+                        if sender
+                            .send((path, buffer.changes.into_inner(), result))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                })
+            });
+        }
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut reported = 0;
+        for (path, changes, result) in receiver {
+            finished.insert(path, (changes, result));
+            while let Some((changes, result)) =
+                repos.get(reported).and_then(|path| finished.remove(path))
+            {
+                let path = &repos[reported];
+                sink.on_repo_start(path);
+                for change in &changes {
+                    sink.on_change(change);
+                }
+                sink.on_repo_done(path, &result);
+                summary.count(&result);
+                reported += 1;
+            }
+        }
+    });
+
+    Ok(summary)
+}
+
+fn pull_path(
+    path: &Path,
+    options: &PullOptions,
+    cache: Option<&Mutex<&mut PullCache>>,
+    sink: &dyn ProgressSink,
+) -> Result<bool> {
+    let repo = Repository::open(path).map_err(|e| Error::Other(e.into()))?;
+    let Some(cache) = cache else {
+        return pull_repo(&repo, options, sink);
+    };
+
+    let path = path.canonicalize()?;
+    let remote_url = git::selected_remote_url(&repo, options.remote.as_deref())?;
+    let now = unix_now();
+    if !options.force && cache.lock().unwrap().is_fresh(&path, &remote_url, now) {
+        debug!("Skipping {}, unchanged since last run", path.display());
+        if !options.quiet {
+            sink.on_change(&SyncOutcome::UpToDate);
+        }
+        return Ok(false);
+    }
+
+    let tracker = RenameTracker {
+        inner: sink,
+        renamed_to: RefCell::new(None),
+    };
+    let changed = pull_repo(&repo, options, &tracker)?;
+
+    // Only a repository that is now fully in sync can be skipped next time
+    let only_reported = options.dry_run || options.print_commands;
+    if (changed && only_reported) || options.no_rename_dir || options.no_rename_remote {
+        return Ok(changed);
+    }
+    let new_path = match tracker.renamed_to.into_inner() {
+        Some(to) => to.canonicalize()?,
+        None => path.clone(),
+    };
+    let repo = Repository::open(&new_path).map_err(|e| Error::Other(e.into()))?;
+    let remote_url = git::selected_remote_url(&repo, options.remote.as_deref())?;
+    let mut cache = cache.lock().unwrap();
+    cache.entries.remove(&path);
+    cache.record(new_path, remote_url, now);
+
+    Ok(changed)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Adds the git repositories up to `depth` levels below `root` to `repos`. A
+/// repository's own subdirectories aren't searched. Only an unreadable `root` is
+/// an error; unreadable directories below it are warned about and skipped.
+fn find_repos(root: &Path, depth: usize, repos: &mut Vec<PathBuf>) -> Result<()> {
+    if depth == 0 {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(root)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if Repository::open(&path).is_ok() {
+            repos.push(path);
+        } else if let Err(e) = find_repos(&path, depth - 1, repos) {
+            warn!("Skipping {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        progress::TextSink,
+        test_helpers,
+        utils::{fs, shell},
+    };
+
+    #[test]
+    fn test_pull_recursive() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let new_name = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let same_name = test_helpers::create_bare_repo(&temp, "same-name.git")?;
+        std::fs::create_dir(temp.path().join("src"))?;
+        let (_, renamed) = test_helpers::create_main_repo(&temp, "src/old-name")?;
+        renamed.remote("origin", &new_name.display().to_string())?;
+        test_helpers::create_main_repo(&temp, "src/no-remote")?;
+        let (_, synced) = test_helpers::create_main_repo(&temp, "src/same-name")?;
+        synced.remote("origin", &same_name.display().to_string())?;
+        // Plain directories are skipped
+        std::fs::create_dir(temp.path().join("src/notes"))?;
+
+        let (output, summary) = test_helpers::capture_stdout(|| {
+            pull_recursive(
+                &temp.path().join("src"),
+                &PullOptions::default(),
+                1,
+                1,
+                None,
+                &TextSink,
+            )
+        })?;
+
+        assert_eq!(
+            summary,
+            BatchSummary {
+                changed: 1,
+                unchanged: 1,
+                failed: 1,
+            }
+        );
+        assert!(
+            output.contains("no remote named 'origin'"),
+            "Expected error for the repository without a remote, got: {}",
+            output
+        );
+        assert!(!output.contains("notes"), "Unexpected output: {}", output);
+        assert_eq!(summary.describe(false), "1 changed, 1 unchanged, 1 failed");
+        test_helpers::assert_directory_existence(&temp, "src/old-name", false)?;
+        test_helpers::assert_directory_existence(&temp, "src/new-name", true)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_recursive_depth() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let upstream = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        std::fs::create_dir_all(temp.path().join("src/org"))?;
+        let (_, nested) = test_helpers::create_main_repo(&temp, "src/org/old-name")?;
+        nested.remote("origin", &upstream.display().to_string())?;
+        // Below a repository, so never searched
+        let (_, inner) = test_helpers::create_main_repo(&temp, "src/org/old-name/vendor")?;
+        inner.remote("origin", &upstream.display().to_string())?;
+
+        let run = |depth| {
+            test_helpers::capture_stdout(|| {
+                pull_recursive(
+                    &temp.path().join("src"),
+                    &PullOptions::default(),
+                    1,
+                    depth,
+                    None,
+                    &TextSink,
+                )
+            })
+            .map(|(_, summary)| summary)
+        };
+
+        assert_eq!(run(1)?, BatchSummary::default());
+        assert_eq!(
+            run(2)?,
+            BatchSummary {
+                changed: 1,
+                ..Default::default()
+            }
+        );
+        assert!(temp.path().join("src/org/new-name/vendor").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_recursive_print_commands() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let new_name = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let same_name = test_helpers::create_bare_repo(&temp, "same-name.git")?;
+        std::fs::create_dir(temp.path().join("src"))?;
+        let (drifting_dir, drifting) = test_helpers::create_main_repo(&temp, "src/old-name")?;
+        drifting.remote("origin", &new_name.display().to_string())?;
+        let (synced_dir, synced) = test_helpers::create_main_repo(&temp, "src/same-name")?;
+        synced.remote("origin", &same_name.display().to_string())?;
+
+        let options = PullOptions {
+            print_commands: true,
+            quiet: true,
+            ..Default::default()
+        };
+        let (output, summary) = test_helpers::capture_stdout(|| {
+            pull_recursive(&temp.path().join("src"), &options, 2, 1, None, &TextSink)
+        })?;
+
+        let drifting_dir = fs::display_path(&drifting_dir);
+        let expected = format!(
+            "{}:\nmv {} {}\n{}:\n",
+            drifting_dir,
+            shell::quote(&drifting_dir),
+            shell::quote(&temp.path().join("src/new-name").display().to_string()),
+            fs::display_path(&synced_dir),
+        );
+        assert_eq!(output, expected);
+        assert_eq!(
+            summary,
+            BatchSummary {
+                changed: 1,
+                unchanged: 1,
+                failed: 0,
+            }
+        );
+        test_helpers::assert_directory_existence(&temp, "src/old-name", true)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_recursive_in_parallel() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        std::fs::create_dir(temp.path().join("src"))?;
+
+        for i in 0..6 {
+            let bare = test_helpers::create_bare_repo(&temp, &format!("new-{}.git", i))?;
+            let (_, repo) = test_helpers::create_main_repo(&temp, &format!("src/old-{}", i))?;
+            repo.remote("upstream", &bare.display().to_string())?;
+        }
+
+        let options = PullOptions {
+            remote: Some("upstream".to_string()),
+            ..Default::default()
+        };
+        let (output, summary) = test_helpers::capture_stdout(|| {
+            pull_recursive(&temp.path().join("src"), &options, 3, 1, None, &TextSink)
+        })?;
+
+        assert_eq!(
+            summary,
+            BatchSummary {
+                changed: 6,
+                unchanged: 0,
+                failed: 0,
+            }
+        );
+        for i in 0..6 {
+            test_helpers::assert_directory_existence(&temp, &format!("src/old-{}", i), false)?;
+            test_helpers::assert_directory_existence(&temp, &format!("src/new-{}", i), true)?;
+        }
+
+        // Each repository's progress is reported together, in path order
+        let headers: Vec<&str> = output.lines().filter(|l| l.ends_with(':')).collect();
+        let expected: Vec<String> = (0..6)
+            .map(|i| format!("{}:", temp.path().join(format!("src/old-{}", i)).display()))
+            .collect();
+        assert_eq!(headers, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_cache_freshness() {
+        let mut cache = PullCache::default();
+        let path = PathBuf::from("/src/repo");
+        cache.record(
+            path.clone(),
+            "https://github.com/owner/repo.git".into(),
+            1000,
+        );
+
+        assert!(cache.is_fresh(&path, "https://github.com/owner/repo.git", 1000));
+        // A changed remote URL, another path or an expired entry means checking again
+        assert!(!cache.is_fresh(&path, "https://github.com/owner/other.git", 1000));
+        assert!(!cache.is_fresh(
+            Path::new("/src/other"),
+            "https://github.com/owner/repo.git",
+            1000
+        ));
+        assert!(!cache.is_fresh(
+            &path,
+            "https://github.com/owner/repo.git",
+            1000 + CACHE_TTL.as_secs()
+        ));
+    }
+
+    #[test]
+    fn test_pull_cache_save_and_load() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let cache_path = temp.path().join("pull-cache.json");
+        let mut cache = PullCache::default();
+        cache.record(
+            PathBuf::from("/src/repo"),
+            "https://github.com/owner/repo.git".into(),
+            1000,
+        );
+
+        cache.save(&cache_path)?;
+
+        let entries: Vec<_> = std::fs::read_dir(temp.path())?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(entries, vec![std::ffi::OsString::from("pull-cache.json")]);
+        let loaded = PullCache::load(&cache_path);
+        assert_eq!(loaded.entries, cache.entries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_cache_skips_print_commands() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let upstream = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        std::fs::create_dir(temp.path().join("src"))?;
+        let (_, repo) = test_helpers::create_main_repo(&temp, "src/old-name")?;
+        repo.remote("origin", &upstream.display().to_string())?;
+
+        let mut cache = PullCache::default();
+        let options = PullOptions {
+            print_commands: true,
+            ..Default::default()
+        };
+        test_helpers::capture_stdout(|| {
+            pull_recursive(
+                &temp.path().join("src"),
+                &options,
+                1,
+                1,
+                Some(&mut cache),
+                &TextSink,
+            )
+        })?;
+
+        // The drift was only printed, so the repository must be checked again
+        assert!(cache.entries.is_empty(), "{:?}", cache.entries);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{git, types::Result};
+
+const SUBCOMMANDS: &[&str] = &[
+    "fetch",
+    "pull",
+    "align",
+    "push",
+    "clone",
+    "resolve-url",
+    "login",
+    "config",
+    "doctor",
+];
+const CONFIG_KEYS: &[&str] = &[
+    "github-token",
+    "github-token-file",
+    "default-remote",
+    "use-upstream",
+    "pull-name-source",
+    "user-agent",
+    "http-timeout",
+    "api-base-url",
+    "github-shorthand",
+    "github-api",
+    "clone-url-template",
+    "oauth-client-id",
+    "post-rename-hook",
+    "audit-log",
+];
+
+/// Returns completion candidates for the given command line words (excluding the
+/// binary name). The last word is the one being completed and may be empty.
+pub fn complete(words: &[String]) -> Result<Vec<String>> {
+    let current = words.last().map(String::as_str).unwrap_or("");
+    let previous = words
+        .len()
+        .checked_sub(2)
+        .and_then(|i| words.get(i))
+        .map(String::as_str);
+
+    let candidates = match previous {
+        None => SUBCOMMANDS.iter().map(|s| s.to_string()).collect(),
+        Some("-r") | Some("--remote") => remote_names(),
+        Some("config") if words.len() == 2 => CONFIG_KEYS.iter().map(|s| s.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(current))
+        .collect())
+}
+
+/// Lists the remotes of the current repository, or nothing outside a repository.
+fn remote_names() -> Vec<String> {
+    git::get_current_repo()
+        .ok()
+        .and_then(|repo| repo.remotes().ok())
+        .map(|remotes| remotes.iter().flatten().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers;
+
+    fn words(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_complete_remote_names() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "test-repo")?;
+        repo.remote("origin", "https://github.com/owner/test-repo.git")?;
+        repo.remote("upstream", "https://github.com/other/test-repo.git")?;
+        std::env::set_current_dir(&repo_dir)?;
+
+        let mut all = complete(&words(&["pull", "--remote", ""]))?;
+        all.sort();
+        assert_eq!(all, vec!["origin", "upstream"]);
+
+        let filtered = complete(&words(&["fetch", "-r", "up"]))?;
+        assert_eq!(filtered, vec!["upstream"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_complete_subcommands_and_config_keys() -> anyhow::Result<()> {
+        assert_eq!(complete(&words(&["pu"]))?, vec!["pull", "push"]);
+        assert_eq!(
+            complete(&words(&["config", "git"]))?,
+            vec![
+                "github-token",
+                "github-token-file",
+                "github-shorthand",
+                "github-api"
+            ]
+        );
+        assert!(complete(&words(&["config", "github-token", ""]))?.is_empty());
+
+        Ok(())
+    }
+}
@@ -3,32 +3,358 @@ use crate::{
     utils,
 };
 use ini::Ini;
+use log::{debug, warn};
 use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 pub static CONFIG: Lazy<Config> = Lazy::new(|| Config::new().expect("Failed to initialize config"));
 
+thread_local! {
+    static SCOPED_CONFIG: RefCell<Option<Config>> = const { RefCell::new(None) };
+}
+
+/// The config library code reads: the one passed to [`with_config`] further up
+/// this thread's stack, otherwise [`CONFIG`].
+pub fn current() -> Config {
+    SCOPED_CONFIG
+        .with(|scoped| scoped.borrow().clone())
+        .unwrap_or_else(|| CONFIG.clone())
+}
+
+/// Runs `f` with `config` in place of [`CONFIG`] on this thread.
+pub fn with_config<R>(config: &Config, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Config>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_CONFIG.with(|scoped| *scoped.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(SCOPED_CONFIG.with(|scoped| scoped.replace(Some(config.clone()))));
+    f()
+}
+
+const DEFAULT_HTTP_TIMEOUT_SECS: f64 = 30.0;
+
+/// Profile sections are named `[profile.NAME]`.
+const PROFILE_SECTION_PREFIX: &str = "profile.";
+
+/// Self-hosted forge sections are named `[provider.NAME]`.
+const PROVIDER_SECTION_PREFIX: &str = "provider.";
+
+/// Per-host GitHub sections are named like git's: `[github "HOST"]`.
+const GITHUB_HOST_SECTION_PREFIX: &str = "github \"";
+
+/// Holds the `version` of the config file layout.
+const META_SECTION: &str = "meta";
+
+/// The config file layout written by this version. Files without a `[meta]`
+/// version predate versioning and are rewritten on load.
+const CONFIG_VERSION: u32 = 1;
+
+fn parse_http_timeout(value: &str) -> Result<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "Invalid HTTP timeout: {}. Expected a positive number of seconds",
+                value
+            ))
+        })
+}
+
+fn parse_clone_url_template(value: &str) -> Result<String> {
+    if value.contains("{owner}") && value.contains("{repo}") {
+        Ok(value.to_string())
+    } else {
+        Err(Error::Config(format!(
+            "Invalid clone URL template: {}. It must contain {{owner}} and {{repo}}",
+            value
+        )))
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.trim() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(Error::Config(format!(
+            "Invalid boolean: {}. Valid values: true, false",
+            value
+        ))),
+    }
+}
+
+/// Parses an optional setting, warning about an invalid value and treating it as
+/// unset, so one bad key falls back to its default instead of breaking every
+/// command.
+fn parse_setting<T>(
+    key: &str,
+    value: Option<&str>,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Option<T> {
+    match parse(value?) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            warn!("{} (config setting '{}'); using the default", e, key);
+            None
+        }
+    }
+}
+
+/// Writes `ini` over `path` through [`utils::fs::write_atomically`], so the file
+/// is never seen half-written and the token in it is never readable by others.
+fn write_ini_atomically(ini: &Ini, path: &Path) -> Result<()> {
+    utils::fs::write_atomically(path, |file| ini.write_to(file))
+        .map_err(|e| Error::Config(format!("Failed to write config file: {}", e)))
+}
+
+/// The first of `config.bak`, `config.bak.1`, `config.bak.2`, ... next to the
+/// config file that doesn't exist yet, so an earlier backup is never overwritten.
+fn unused_backup_path(config_file: &Path) -> PathBuf {
+    let backup = config_file.with_extension("bak");
+    std::iter::once(backup.clone())
+        .chain((1..).map(|n| backup.with_extension(format!("bak.{}", n))))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Settings loaded from a config file, plus per-run overrides like `--remote`.
+/// Clones share their settings, so a change made through one is seen by all.
+#[derive(Clone)]
 pub struct Config {
     config_dir: PathBuf,
-    config_values: RwLock<ConfigValues>,
+    config_values: Arc<RwLock<ConfigValues>>,
+    /// The GitHub host of the remote being worked on, whose `[github "HOST"]`
+    /// settings take precedence. Unlike the other settings, not shared by clones.
+    github_host: Option<String>,
 }
 
 /// Internal configuration values that are loaded from the config file.
 #[derive(Clone)]
 struct ConfigValues {
     github_token: Option<String>,
+    github_token_file: Option<PathBuf>,
     // Current remote, None means use default_remote
     remote: Option<String>,
+    // Repository to operate on (-C), None means discover from the current directory
+    repo_path: Option<PathBuf>,
     default_remote: String,
+    /// Use the remote the current branch tracks instead of the default remote.
+    use_upstream: bool,
+    pull_name_source: PullNameSource,
+    user_agent: Option<String>,
+    /// HTTP request timeout in seconds.
+    http_timeout: Option<f64>,
+    api_base_url: Option<String>,
+    /// Treat bare `owner/repo` remotes as GitHub shorthand.
+    github_shorthand: bool,
+    github_api: GitHubApiBackend,
+    /// Shell command run after the working directory is renamed.
+    post_rename_hook: Option<String>,
+    /// File that every directory rename and remote URL change is appended to.
+    audit_log: Option<PathBuf>,
+    /// Remote URL format for renamed GitHub repositories, with `{owner}` and `{repo}`
+    /// placeholders, e.g. for a mirror.
+    clone_url_template: Option<String>,
+    /// Client ID of the OAuth app `login` authorizes through the device flow.
+    oauth_client_id: Option<String>,
+    /// Active profile (`--profile`), None means use the top-level settings only.
+    profile: Option<String>,
+    profiles: BTreeMap<String, ProfileValues>,
+    /// Self-hosted forges, in the order they appear in the config file.
+    providers: Vec<ForgeProvider>,
+    /// `[github "HOST"]` sections, by lowercase host.
+    github_hosts: BTreeMap<String, GitHubHostValues>,
+}
+
+/// Settings a `[github "HOST"]` section sets for remotes on that host.
+#[derive(Clone, Default)]
+struct GitHubHostValues {
+    token: Option<String>,
+    api_base_url: Option<String>,
+}
+
+/// Settings a `[profile.NAME]` section overrides for that profile.
+#[derive(Clone, Default)]
+struct ProfileValues {
+    github_token: Option<String>,
+    github_token_file: Option<PathBuf>,
+    /// A GitHub Enterprise host, used for the API base URL unless `api_base_url` is set.
+    github_host: Option<String>,
+    api_base_url: Option<String>,
+    default_remote: Option<String>,
+}
+
+impl ConfigValues {
+    fn active_profile(&self) -> Option<&ProfileValues> {
+        self.profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    /// The active profile, created on first write. None when no profile is selected.
+    fn active_profile_mut(&mut self) -> Option<&mut ProfileValues> {
+        let name = self.profile.clone()?;
+        Some(self.profiles.entry(name).or_default())
+    }
+}
+
+impl Default for ConfigValues {
+    fn default() -> Self {
+        Self {
+            github_token: None,
+            github_token_file: None,
+            remote: None,
+            repo_path: None,
+            default_remote: "origin".to_string(),
+            use_upstream: false,
+            pull_name_source: PullNameSource::default(),
+            user_agent: None,
+            http_timeout: None,
+            api_base_url: None,
+            github_shorthand: false,
+            github_api: GitHubApiBackend::default(),
+            post_rename_hook: None,
+            audit_log: None,
+            clone_url_template: None,
+            oauth_client_id: None,
+            profile: None,
+            profiles: BTreeMap::new(),
+            providers: Vec::new(),
+            github_hosts: BTreeMap::new(),
+        }
+    }
+}
+
+/// Where `pull` takes the canonical repository name from for GitHub remotes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PullNameSource {
+    /// The `name` returned by the GitHub API.
+    #[default]
+    Api,
+    /// The repository segment of the remote URL.
+    Url,
+}
+
+impl std::str::FromStr for PullNameSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "api" => Ok(PullNameSource::Api),
+            "url" => Ok(PullNameSource::Url),
+            _ => Err(Error::Config(format!(
+                "Invalid pull name source: {}. Valid values: api, url",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for PullNameSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PullNameSource::Api => write!(f, "api"),
+            PullNameSource::Url => write!(f, "url"),
+        }
+    }
+}
+
+/// Which GitHub API renames repositories.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GitHubApiBackend {
+    /// `PATCH /repos/{owner}/{repo}`.
+    #[default]
+    Rest,
+    /// The `updateRepository` mutation, for proxies that only allow `/graphql`.
+    Graphql,
+}
+
+impl std::str::FromStr for GitHubApiBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rest" => Ok(GitHubApiBackend::Rest),
+            "graphql" => Ok(GitHubApiBackend::Graphql),
+            _ => Err(Error::Config(format!(
+                "Invalid GitHub API: {}. Valid values: rest, graphql",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for GitHubApiBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubApiBackend::Rest => write!(f, "rest"),
+            GitHubApiBackend::Graphql => write!(f, "graphql"),
+        }
+    }
+}
+
+/// The API a self-hosted forge speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeKind {
+    /// Gitea (and Forgejo), whose `/api/v1` mirrors GitHub's repository endpoints.
+    Gitea,
+}
+
+impl std::str::FromStr for ForgeKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gitea" => Ok(ForgeKind::Gitea),
+            _ => Err(Error::Config(format!(
+                "Invalid provider kind: {}. Valid values: gitea",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for ForgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForgeKind::Gitea => write!(f, "gitea"),
+        }
+    }
+}
+
+/// A self-hosted forge declared in a `[provider.NAME]` section. Remotes on `host`
+/// are resolved and renamed through its API instead of GitHub's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeProvider {
+    pub name: String,
+    /// The host remote URLs point at, e.g. `git.example.com`.
+    pub host: String,
+    /// The API endpoint, e.g. `https://git.example.com/api/v1`.
+    pub api_base: String,
+    pub kind: ForgeKind,
+    pub token: Option<String>,
 }
 
 impl Config {
     pub fn new() -> Result<Self> {
-        let config_dir = Self::get_config_dir()?;
+        Self::load_from(Self::get_config_dir()?)
+    }
 
+    /// Loads the config file in `config_dir`, creating it (and the directory) if
+    /// it doesn't exist. Unlike [`CONFIG`], the result isn't shared with the rest
+    /// of the process until passed to [`with_config`] or an `_with` function.
+    pub fn load_from(config_dir: PathBuf) -> Result<Self> {
         if !config_dir.exists() {
             fs::create_dir_all(&config_dir)
                 .map_err(|e| Error::Config(format!("Failed to create config directory: {}", e)))?;
@@ -36,19 +362,38 @@ impl Config {
 
         let config = Self {
             config_dir,
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
 
         // Check if config file exists and load it if it does
         let config_file = config.get_config_file_path();
         if config_file.exists() {
-            let ini = Ini::load_from_file(&config_file)
-                .map_err(|e| Error::Config(format!("Failed to read config file: {}", e)))?;
-            config.load_from_ini(&ini)?;
+            match Ini::load_from_file(&config_file) {
+                Ok(ini) => {
+                    config.load_from_ini(&ini)?;
+                    if ini.get_from(Some(META_SECTION), "version").is_none() {
+                        config.migrate()?;
+                    }
+                }
+                Err(ini::Error::Parse(e)) => {
+                    // A corrupt file shouldn't break every command; keep it for inspection
+                    let backup = unused_backup_path(&config_file);
+                    warn!(
+                        "Config file '{}' is malformed ({}); moved it to '{}' and started with default settings",
+                        config_file.display(),
+                        e,
+                        backup.display()
+                    );
+                    fs::rename(&config_file, &backup).map_err(|e| {
+                        Error::Config(format!("Failed to back up config file: {}", e))
+                    })?;
+                    config.write_to_disk()?;
+                }
+                Err(e) => {
+                    return Err(Error::Config(format!("Failed to read config file: {}", e)));
+                }
+            }
         } else {
             // Create initial config file
             config.write_to_disk()?;
@@ -77,65 +422,533 @@ impl Config {
             .get_from(Some("github"), "token")
             .map(String::from)
             .filter(|s| !s.is_empty());
+        values.github_token_file = ini
+            .get_from(Some("github"), "token_file")
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from);
         values.default_remote = ini
             .get_from(None::<String>, "default_remote")
             .unwrap_or("origin")
             .to_string();
+        values.use_upstream = parse_setting(
+            "use_upstream",
+            ini.get_from(None::<String>, "use_upstream"),
+            parse_bool,
+        )
+        .unwrap_or(false);
+        values.pull_name_source = parse_setting(
+            "pull_name_source",
+            ini.get_from(None::<String>, "pull_name_source"),
+            str::parse,
+        )
+        .unwrap_or_default();
+        values.user_agent = ini
+            .get_from(None::<String>, "user_agent")
+            .map(String::from)
+            .filter(|s| !s.is_empty());
+        values.http_timeout = parse_setting(
+            "http_timeout",
+            ini.get_from(None::<String>, "http_timeout"),
+            parse_http_timeout,
+        );
+        values.api_base_url = ini
+            .get_from(Some("github"), "api_base_url")
+            .map(String::from)
+            .filter(|s| !s.is_empty());
+        values.github_shorthand = parse_setting(
+            "github.shorthand",
+            ini.get_from(Some("github"), "shorthand"),
+            parse_bool,
+        )
+        .unwrap_or(false);
+        values.github_api = parse_setting(
+            "github.api",
+            ini.get_from(Some("github"), "api"),
+            str::parse,
+        )
+        .unwrap_or_default();
+        values.post_rename_hook = ini
+            .get_from(None::<String>, "post_rename_hook")
+            .map(String::from)
+            .filter(|s| !s.is_empty());
+        values.audit_log = ini
+            .get_from(None::<String>, "audit_log")
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from);
+        values.clone_url_template = parse_setting(
+            "github.clone_url_template",
+            ini.get_from(Some("github"), "clone_url_template")
+                .filter(|s| !s.is_empty()),
+            parse_clone_url_template,
+        );
+        values.oauth_client_id = ini
+            .get_from(Some("github"), "client_id")
+            .map(String::from)
+            .filter(|s| !s.is_empty());
+
+        values.providers.clear();
+        for (section, properties) in ini.iter() {
+            let Some(name) = section.and_then(|s| s.strip_prefix(PROVIDER_SECTION_PREFIX)) else {
+                continue;
+            };
+            let get = |key: &str| {
+                properties
+                    .get(key)
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| {
+                        Error::Config(format!(
+                            "Provider '{}' is missing the '{}' setting",
+                            name, key
+                        ))
+                    })
+            };
+            values.providers.push(ForgeProvider {
+                name: name.to_string(),
+                host: get("host")?.to_string(),
+                api_base: get("api_base")?.trim_end_matches('/').to_string(),
+                kind: get("kind")?.parse()?,
+                token: get("token").ok().map(String::from),
+            });
+        }
+
+        values.github_hosts.clear();
+        for (section, properties) in ini.iter() {
+            let Some(host) = section
+                .and_then(|s| s.strip_prefix(GITHUB_HOST_SECTION_PREFIX))
+                .and_then(|s| s.strip_suffix('"'))
+            else {
+                continue;
+            };
+            let get = |key: &str| properties.get(key).filter(|s| !s.is_empty());
+            values.github_hosts.insert(
+                host.to_lowercase(),
+                GitHubHostValues {
+                    token: get("token").map(String::from),
+                    api_base_url: get("api_base_url").map(String::from),
+                },
+            );
+        }
+
+        values.profiles.clear();
+        for (section, properties) in ini.iter() {
+            let Some(name) = section.and_then(|s| s.strip_prefix(PROFILE_SECTION_PREFIX)) else {
+                continue;
+            };
+            let get = |key: &str| properties.get(key).filter(|s| !s.is_empty());
+            values.profiles.insert(
+                name.to_string(),
+                ProfileValues {
+                    github_token: get("token").map(String::from),
+                    github_token_file: get("token_file").map(PathBuf::from),
+                    github_host: get("github_host").map(String::from),
+                    api_base_url: get("api_base_url").map(String::from),
+                    default_remote: get("default_remote").map(String::from),
+                },
+            );
+        }
         Ok(())
     }
 
+    /// Rewrites an unversioned config file in the current layout. Every setting
+    /// was already loaded, so it carries over as is.
+    fn migrate(&self) -> Result<()> {
+        let config_file = self.get_config_file_path();
+        debug!(
+            "Migrating config file '{}' to version {}",
+            config_file.display(),
+            CONFIG_VERSION
+        );
+        write_ini_atomically(&self.to_ini(), &config_file)
+    }
+
     fn write_to_disk(&self) -> Result<()> {
+        let ini = self.to_ini();
+
+        let config_file = self.get_config_file_path();
+        if let Some(parent) = config_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        write_ini_atomically(&ini, &config_file)
+    }
+
+    fn to_ini(&self) -> Ini {
         let values = self.config_values.read().unwrap();
         let mut ini = Ini::new();
 
+        ini.with_section(Some(META_SECTION))
+            .set("version", CONFIG_VERSION.to_string());
+
         if let Some(token) = &values.github_token {
             ini.with_section(Some("github"))
                 .set("token".to_string(), token.clone());
         }
+        if let Some(token_file) = &values.github_token_file {
+            ini.with_section(Some("github"))
+                .set("token_file".to_string(), token_file.display().to_string());
+        }
+        if let Some(api_base_url) = &values.api_base_url {
+            ini.with_section(Some("github"))
+                .set("api_base_url".to_string(), api_base_url.clone());
+        }
+        if let Some(template) = &values.clone_url_template {
+            ini.with_section(Some("github"))
+                .set("clone_url_template".to_string(), template.clone());
+        }
+        if let Some(client_id) = &values.oauth_client_id {
+            ini.with_section(Some("github"))
+                .set("client_id".to_string(), client_id.clone());
+        }
+        if values.github_shorthand {
+            ini.with_section(Some("github"))
+                .set("shorthand".to_string(), "true".to_string());
+        }
+        if values.github_api != GitHubApiBackend::Rest {
+            ini.with_section(Some("github"))
+                .set("api".to_string(), values.github_api.to_string());
+        }
 
         // Write default remote
         ini.with_section(None::<String>)
-            .set("default_remote".to_string(), values.default_remote.clone());
+            .set("default_remote".to_string(), values.default_remote.clone())
+            .set(
+                "pull_name_source".to_string(),
+                values.pull_name_source.to_string(),
+            );
 
-        let config_file = self.get_config_file_path();
-        if let Some(parent) = config_file.parent() {
-            std::fs::create_dir_all(parent)?;
+        if values.use_upstream {
+            ini.with_section(None::<String>)
+                .set("use_upstream".to_string(), "true".to_string());
+        }
+        if let Some(user_agent) = &values.user_agent {
+            ini.with_section(None::<String>)
+                .set("user_agent".to_string(), user_agent.clone());
+        }
+        if let Some(http_timeout) = values.http_timeout {
+            ini.with_section(None::<String>)
+                .set("http_timeout".to_string(), http_timeout.to_string());
+        }
+        if let Some(hook) = &values.post_rename_hook {
+            ini.with_section(None::<String>)
+                .set("post_rename_hook".to_string(), hook.clone());
+        }
+        if let Some(audit_log) = &values.audit_log {
+            ini.with_section(None::<String>)
+                .set("audit_log".to_string(), audit_log.display().to_string());
         }
 
-        ini.write_to_file(&config_file)
-            .map_err(|e| Error::Config(format!("Failed to write config file: {}", e)))?;
+        for (name, profile) in &values.profiles {
+            let mut section = ini.with_section(Some(format!("{}{}", PROFILE_SECTION_PREFIX, name)));
+            let settings = [
+                ("token", profile.github_token.clone()),
+                (
+                    "token_file",
+                    profile
+                        .github_token_file
+                        .as_ref()
+                        .map(|path| path.display().to_string()),
+                ),
+                ("github_host", profile.github_host.clone()),
+                ("api_base_url", profile.api_base_url.clone()),
+                ("default_remote", profile.default_remote.clone()),
+            ];
+            for (key, value) in settings {
+                if let Some(value) = value {
+                    section.set(key, value);
+                }
+            }
+        }
 
-        utils::fs::set_secure_permissions(&config_file)?;
+        for provider in &values.providers {
+            let mut section = ini.with_section(Some(format!(
+                "{}{}",
+                PROVIDER_SECTION_PREFIX, provider.name
+            )));
+            section
+                .set("host", provider.host.clone())
+                .set("api_base", provider.api_base.clone())
+                .set("kind", provider.kind.to_string());
+            if let Some(token) = &provider.token {
+                section.set("token", token.clone());
+            }
+        }
 
-        Ok(())
+        for (host, host_values) in &values.github_hosts {
+            let mut section =
+                ini.with_section(Some(format!("{}{}\"", GITHUB_HOST_SECTION_PREFIX, host)));
+            if let Some(token) = &host_values.token {
+                section.set("token", token.clone());
+            }
+            if let Some(api_base_url) = &host_values.api_base_url {
+                section.set("api_base_url", api_base_url.clone());
+            }
+        }
+
+        ini
+    }
+
+    /// A copy of this config for a remote on the GitHub `host`: its `[github "HOST"]`
+    /// section, if there is one, takes precedence over the token and API endpoint.
+    pub fn for_github_host(&self, host: &str) -> Config {
+        Config {
+            github_host: Some(host.to_lowercase()),
+            ..self.clone()
+        }
+    }
+
+    /// Hosts with a `[github "HOST"]` section.
+    pub fn get_github_hosts(&self) -> Vec<String> {
+        let values = self.config_values.read().unwrap();
+        values.github_hosts.keys().cloned().collect()
     }
 
-    fn get_config_file_path(&self) -> PathBuf {
+    fn github_host_values<'a>(&self, values: &'a ConfigValues) -> Option<&'a GitHubHostValues> {
+        values.github_hosts.get(self.github_host.as_ref()?)
+    }
+
+    pub fn get_config_file_path(&self) -> PathBuf {
         self.config_dir.join("config")
     }
 
+    /// Where `pull --recursive --cache` keeps the repositories it found in sync.
+    pub fn get_pull_cache_path(&self) -> PathBuf {
+        self.config_dir.join("pull-cache.json")
+    }
+
+    /// Returns the GitHub token, preferring a stored token, then a token file
+    /// (`github-token-file` or `GITHUB_TOKEN_FILE`), then the `GITHUB_TOKEN`
+    /// environment variable. Token files are read on every call so rotated
+    /// secrets are picked up and never copied into the config file.
     pub fn get_github_token(&self) -> Result<String> {
         let values = self.config_values.read().unwrap();
-        values
-            .github_token
-            .clone()
+        if let Some(token) = self
+            .github_host_values(&values)
+            .and_then(|h| h.token.clone())
+        {
+            return Ok(token);
+        }
+        // A profile that sets either a token or a token file replaces both
+        let (token, token_file) = match values
+            .active_profile()
+            .filter(|p| p.github_token.is_some() || p.github_token_file.is_some())
+        {
+            Some(profile) => (
+                profile.github_token.clone(),
+                profile.github_token_file.clone(),
+            ),
+            None => (
+                values.github_token.clone(),
+                values.github_token_file.clone(),
+            ),
+        };
+        if let Some(token) = token.filter(|t| !t.is_empty()) {
+            return Ok(token);
+        }
+
+        let token_file = token_file.or_else(|| env::var_os("GITHUB_TOKEN_FILE").map(PathBuf::from));
+        drop(values);
+        if let Some(token_file) = token_file {
+            let token = fs::read_to_string(&token_file).map_err(|e| {
+                Error::Config(format!(
+                    "Failed to read GitHub token file '{}': {}",
+                    token_file.display(),
+                    e
+                ))
+            })?;
+            return Ok(token.trim().to_string());
+        }
+
+        env::var("GITHUB_TOKEN")
+            .ok()
+            .filter(|t| !t.is_empty())
             .ok_or_else(|| Error::Config("No GitHub token found in configuration".into()))
     }
 
     pub fn set_github_token(&self, token: &str) -> Result<()> {
         let mut values = self.config_values.write().unwrap();
-        values.github_token = Some(token.to_string());
+        match values.active_profile_mut() {
+            Some(profile) => {
+                profile.github_token = Some(token.to_string()).filter(|t| !t.is_empty())
+            }
+            None => values.github_token = Some(token.to_string()),
+        }
         drop(values);
         self.write_to_disk()
     }
 
+    pub fn get_github_token_file(&self) -> Option<PathBuf> {
+        let values = self.config_values.read().unwrap();
+        values
+            .active_profile()
+            .and_then(|p| p.github_token_file.clone())
+            .or_else(|| values.github_token_file.clone())
+    }
+
+    /// Sets the file the GitHub token is read from. An empty path clears it.
+    pub fn set_github_token_file(&self, path: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        let token_file = Some(PathBuf::from(path)).filter(|_| !path.is_empty());
+        match values.active_profile_mut() {
+            Some(profile) => profile.github_token_file = token_file,
+            None => values.github_token_file = token_file,
+        }
+        drop(values);
+        self.write_to_disk()
+    }
+
+    /// The configured GitHub API endpoint. A profile's `github_host` stands for
+    /// that GitHub Enterprise host's `/api/v3` endpoint, and so does the host of a
+    /// `[github "HOST"]` section for remotes on that host.
+    pub fn get_api_base_url(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        if let Some(host_values) = self.github_host_values(&values) {
+            let host = self.github_host.as_deref().unwrap_or_default();
+            return host_values
+                .api_base_url
+                .clone()
+                .or_else(|| (host != "github.com").then(|| format!("https://{}/api/v3", host)));
+        }
+        let profile = values.active_profile();
+        profile
+            .and_then(|p| p.api_base_url.clone())
+            .or_else(|| {
+                profile
+                    .and_then(|p| p.github_host.as_ref())
+                    .map(|host| format!("https://{}/api/v3", host))
+            })
+            .or_else(|| values.api_base_url.clone())
+    }
+
+    /// Sets the GitHub API endpoint (e.g. for GitHub Enterprise). An empty value
+    /// restores the default.
+    pub fn set_api_base_url(&self, url: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        let url = Some(url.trim_end_matches('/').to_string()).filter(|s| !s.is_empty());
+        match values.active_profile_mut() {
+            Some(profile) => profile.api_base_url = url,
+            None => values.api_base_url = url,
+        }
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_post_rename_hook(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        values.post_rename_hook.clone()
+    }
+
+    /// Sets the command run after a directory rename. An empty value removes it.
+    pub fn set_post_rename_hook(&self, command: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.post_rename_hook = Some(command.to_string()).filter(|s| !s.trim().is_empty());
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_audit_log(&self) -> Option<PathBuf> {
+        let values = self.config_values.read().unwrap();
+        values.audit_log.clone()
+    }
+
+    /// Sets the file renames and remote changes are logged to. An empty value
+    /// turns logging off.
+    pub fn set_audit_log(&self, path: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.audit_log = Some(PathBuf::from(path)).filter(|_| !path.is_empty());
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_clone_url_template(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        values.clone_url_template.clone()
+    }
+
+    /// Sets the remote URL format for renamed GitHub repositories. An empty value
+    /// removes it.
+    pub fn set_clone_url_template(&self, template: &str) -> Result<()> {
+        let template = match template.trim() {
+            "" => None,
+            template => Some(parse_clone_url_template(template)?),
+        };
+        let mut values = self.config_values.write().unwrap();
+        values.clone_url_template = template;
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_oauth_client_id(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        values.oauth_client_id.clone()
+    }
+
+    /// Sets the OAuth app client ID used by `login`. An empty value removes it.
+    pub fn set_oauth_client_id(&self, client_id: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.oauth_client_id = Some(client_id.trim().to_string()).filter(|s| !s.is_empty());
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_github_api(&self) -> GitHubApiBackend {
+        let values = self.config_values.read().unwrap();
+        values.github_api
+    }
+
+    pub fn set_github_api(&self, backend: GitHubApiBackend) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.github_api = backend;
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_github_shorthand(&self) -> bool {
+        let values = self.config_values.read().unwrap();
+        values.github_shorthand
+    }
+
+    pub fn set_github_shorthand(&self, value: &str) -> Result<()> {
+        let enabled = parse_bool(value)?;
+        let mut values = self.config_values.write().unwrap();
+        values.github_shorthand = enabled;
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_use_upstream(&self) -> bool {
+        let values = self.config_values.read().unwrap();
+        values.use_upstream
+    }
+
+    pub fn set_use_upstream(&self, value: &str) -> Result<()> {
+        let enabled = parse_bool(value)?;
+        let mut values = self.config_values.write().unwrap();
+        values.use_upstream = enabled;
+        drop(values);
+        self.write_to_disk()
+    }
+
+    /// Forgets the `--remote` override, going back to the configured remote.
+    pub fn clear_remote(&self) {
+        let mut values = self.config_values.write().unwrap();
+        values.remote = None;
+    }
+
+    /// The remote given with `--remote` for this run, if any.
+    pub fn get_remote_override(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        values.remote.clone()
+    }
+
     pub fn get_remote(&self) -> Result<String> {
         let values = self.config_values.read().unwrap();
-        Ok(values
-            .remote
-            .as_ref()
-            .unwrap_or(&values.default_remote)
-            .clone())
+        match &values.remote {
+            Some(remote) => Ok(remote.clone()),
+            None => {
+                drop(values);
+                self.get_default_remote()
+            }
+        }
     }
 
     pub fn set_remote(&self, remote: String) {
@@ -143,14 +956,138 @@ impl Config {
         values.remote = Some(remote);
     }
 
+    pub fn get_profile(&self) -> Option<String> {
+        let values = self.config_values.read().unwrap();
+        values.profile.clone()
+    }
+
+    /// Selects the `[profile.NAME]` section whose settings override the top-level
+    /// ones. Unknown profiles are rejected unless `create` is set, in which case
+    /// the section is written on the first change.
+    pub fn set_profile(&self, name: Option<String>, create: bool) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        if let Some(name) = &name {
+            if !create && !values.profiles.contains_key(name) {
+                return Err(Error::Config(format!(
+                    "Unknown profile '{}'. Add a [{}{}] section to {}",
+                    name,
+                    PROFILE_SECTION_PREFIX,
+                    name,
+                    self.get_config_file_path().display()
+                )));
+            }
+        }
+        values.profile = name;
+        Ok(())
+    }
+
+    /// The self-hosted forge serving remotes on `host`, if one is configured.
+    pub fn get_provider_for_host(&self, host: &str) -> Option<ForgeProvider> {
+        let values = self.config_values.read().unwrap();
+        values
+            .providers
+            .iter()
+            .find(|provider| provider.host.eq_ignore_ascii_case(host))
+            .cloned()
+    }
+
+    /// Adds a `[provider.NAME]` section, replacing one with the same name.
+    pub fn set_provider(&self, provider: ForgeProvider) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.providers.retain(|p| p.name != provider.name);
+        values.providers.push(provider);
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn remove_provider(&self, name: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.providers.retain(|p| p.name != name);
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_repo_path(&self) -> Option<PathBuf> {
+        let values = self.config_values.read().unwrap();
+        values.repo_path.clone()
+    }
+
+    pub fn set_repo_path(&self, path: Option<PathBuf>) {
+        let mut values = self.config_values.write().unwrap();
+        values.repo_path = path;
+    }
+
     pub fn get_default_remote(&self) -> Result<String> {
         let values = self.config_values.read().unwrap();
-        Ok(values.default_remote.clone())
+        Ok(values
+            .active_profile()
+            .and_then(|p| p.default_remote.clone())
+            .unwrap_or_else(|| values.default_remote.clone()))
     }
 
     pub fn set_default_remote(&self, remote: &str) -> Result<()> {
         let mut values = self.config_values.write().unwrap();
-        values.default_remote = remote.to_string();
+        match values.active_profile_mut() {
+            Some(profile) => profile.default_remote = Some(remote.to_string()),
+            None => values.default_remote = remote.to_string(),
+        }
+        drop(values);
+        self.write_to_disk()
+    }
+
+    pub fn get_pull_name_source(&self) -> Result<PullNameSource> {
+        let values = self.config_values.read().unwrap();
+        Ok(values.pull_name_source)
+    }
+
+    pub fn set_pull_name_source(&self, source: PullNameSource) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.pull_name_source = source;
+        drop(values);
+        self.write_to_disk()
+    }
+
+    /// The User-Agent sent with GitHub API requests. The `GIT_REPO_NAME_USER_AGENT`
+    /// environment variable takes precedence over the configured value.
+    pub fn get_user_agent(&self) -> String {
+        if let Some(user_agent) = env::var("GIT_REPO_NAME_USER_AGENT")
+            .ok()
+            .filter(|s| !s.is_empty())
+        {
+            return user_agent;
+        }
+
+        let values = self.config_values.read().unwrap();
+        values
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| format!("git-repo-name/{}", env!("CARGO_PKG_VERSION")))
+    }
+
+    pub fn set_user_agent(&self, user_agent: &str) -> Result<()> {
+        let mut values = self.config_values.write().unwrap();
+        values.user_agent = Some(user_agent.to_string()).filter(|s| !s.is_empty());
+        drop(values);
+        self.write_to_disk()
+    }
+
+    /// The overall timeout for GitHub API requests. The `GIT_REPO_NAME_HTTP_TIMEOUT`
+    /// environment variable (in seconds) takes precedence over the configured value.
+    pub fn get_http_timeout(&self) -> Result<Duration> {
+        let seconds = match env::var("GIT_REPO_NAME_HTTP_TIMEOUT") {
+            Ok(value) if !value.is_empty() => parse_http_timeout(&value)?,
+            _ => {
+                let values = self.config_values.read().unwrap();
+                values.http_timeout.unwrap_or(DEFAULT_HTTP_TIMEOUT_SECS)
+            }
+        };
+        Ok(Duration::from_secs_f64(seconds))
+    }
+
+    pub fn set_http_timeout(&self, seconds: &str) -> Result<()> {
+        let timeout = parse_http_timeout(seconds)?;
+        let mut values = self.config_values.write().unwrap();
+        values.http_timeout = Some(timeout);
         drop(values);
         self.write_to_disk()
     }
@@ -169,11 +1106,8 @@ mod tests {
         let temp = assert_fs::TempDir::new()?;
         let config = Config {
             config_dir: temp.path().to_path_buf(),
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
         config.write_to_disk()?;
 
@@ -196,11 +1130,8 @@ mod tests {
 
         let new_config = Config {
             config_dir: temp.path().to_path_buf(),
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
         let ini = Ini::load_from_file(&config_file)?;
         new_config.load_from_ini(&ini)?;
@@ -215,11 +1146,8 @@ mod tests {
         let temp = assert_fs::TempDir::new()?;
         let config = Config {
             config_dir: temp.path().to_path_buf(),
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
         config.write_to_disk()?;
 
@@ -230,13 +1158,10 @@ mod tests {
 
         let new_config = Config {
             config_dir: temp.path().to_path_buf(),
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
-        let ini = Ini::load_from_file(&temp.child("config"))?;
+        let ini = Ini::load_from_file(temp.child("config"))?;
         new_config.load_from_ini(&ini)?;
         assert_eq!(new_config.get_remote()?, "upstream");
 
@@ -246,6 +1171,140 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_github_host_sections() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("config").write_str(
+            "[github]\n\
+             token=default-token\n\
+             [github \"github.acme.internal\"]\n\
+             token=acme-token\n",
+        )?;
+        let config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        config.load_from_ini(&Ini::load_from_file(temp.child("config"))?)?;
+
+        let acme = config.for_github_host("GitHub.Acme.Internal");
+        assert_eq!(acme.get_github_token()?, "acme-token");
+        assert_eq!(
+            acme.get_api_base_url().as_deref(),
+            Some("https://github.acme.internal/api/v3")
+        );
+        // Hosts without a section, and the original config, use the defaults
+        let other = config.for_github_host("github.com");
+        assert_eq!(other.get_github_token()?, "default-token");
+        assert_eq!(other.get_api_base_url(), None);
+        assert_eq!(config.get_github_token()?, "default-token");
+
+        // The section survives a rewrite of the file
+        config.write_to_disk()?;
+        let written = std::fs::read_to_string(temp.child("config").path())?;
+        assert!(
+            written.contains("[github \"github.acme.internal\"]"),
+            "config: {}",
+            written
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profiles() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("config").write_str(
+            "default_remote=origin\n\
+             [github]\n\
+             token=default-token\n\
+             [profile.work]\n\
+             token=work-token\n\
+             github_host=github.acme.internal\n\
+             default_remote=upstream\n\
+             [profile.personal]\n\
+             token=personal-token\n",
+        )?;
+        let config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        config.load_from_ini(&Ini::load_from_file(temp.child("config"))?)?;
+
+        assert_eq!(config.get_github_token()?, "default-token");
+        assert_eq!(config.get_remote()?, "origin");
+
+        config.set_profile(Some("work".to_string()), false)?;
+        assert_eq!(config.get_github_token()?, "work-token");
+        assert_eq!(
+            config.get_api_base_url().as_deref(),
+            Some("https://github.acme.internal/api/v3")
+        );
+        assert_eq!(config.get_remote()?, "upstream");
+
+        config.set_profile(Some("personal".to_string()), false)?;
+        assert_eq!(config.get_github_token()?, "personal-token");
+        assert_eq!(config.get_api_base_url(), None);
+        assert_eq!(config.get_remote()?, "origin");
+
+        assert!(config
+            .set_profile(Some("missing".to_string()), false)
+            .is_err());
+
+        // Writes go to the active profile and leave the others alone
+        config.set_github_token("rotated-token")?;
+        let ini = Ini::load_from_file(temp.child("config"))?;
+        assert_eq!(
+            ini.get_from(Some("profile.personal"), "token"),
+            Some("rotated-token")
+        );
+        assert_eq!(
+            ini.get_from(Some("profile.work"), "token"),
+            Some("work-token")
+        );
+        assert_eq!(ini.get_from(Some("github"), "token"), Some("default-token"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_providers() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("config").write_str(
+            "[provider.mygitea]\n\
+             host=git.example.com\n\
+             api_base=https://git.example.com/api/v1/\n\
+             kind=gitea\n",
+        )?;
+        let config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        config.load_from_ini(&Ini::load_from_file(temp.child("config"))?)?;
+
+        let provider = config.get_provider_for_host("GIT.example.com");
+        assert_eq!(
+            provider,
+            Some(ForgeProvider {
+                name: "mygitea".to_string(),
+                host: "git.example.com".to_string(),
+                api_base: "https://git.example.com/api/v1".to_string(),
+                kind: ForgeKind::Gitea,
+                token: None,
+            })
+        );
+        assert_eq!(config.get_provider_for_host("github.com"), None);
+
+        let mut ini = Ini::new();
+        ini.with_section(Some("provider.broken"))
+            .set("host", "git.example.com");
+        assert!(config.load_from_ini(&ini).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_malformed_config_file() -> anyhow::Result<()> {
         let temp = assert_fs::TempDir::new()?;
@@ -254,17 +1313,139 @@ mod tests {
         if let Some(parent) = config_file.path().parent() {
             std::fs::create_dir_all(parent)?;
         }
-        config_file.write_str("not a valid ini file")?;
+        config_file.write_str("[github\ntoken = \"unterminated")?;
 
         let config_result = Config::new();
-        assert!(
-            config_result.is_err(),
-            "Expected error due to malformed config file"
-        );
 
         // Clean up the environment variable
         env::remove_var("XDG_CONFIG_HOME");
 
+        let config = config_result?;
+        assert_eq!(config.get_remote()?, "origin");
+        temp.child("git-repo-name")
+            .child("config.bak")
+            .assert("[github\ntoken = \"unterminated");
+        let ini = Ini::load_from_file(config_file.path())?;
+        assert_eq!(
+            ini.get_from(Some(META_SECTION), "version"),
+            Some(CONFIG_VERSION.to_string().as_str())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_config_file_keeps_earlier_backups() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let config_file = temp.child("config");
+
+        for content in ["[first", "[second", "[third"] {
+            config_file.write_str(content)?;
+            Config::load_from(temp.path().to_path_buf())?;
+        }
+
+        temp.child("config.bak").assert("[first");
+        temp.child("config.bak.1").assert("[second");
+        temp.child("config.bak.2").assert("[third");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config_values_fall_back_to_defaults() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("config").write_str(
+            "http_timeout=abc\n\
+             pull_name_source=display\n\
+             use_upstream=maybe\n\
+             default_remote=upstream\n\
+             [github]\n\
+             api=soap\n\
+             shorthand=sometimes\n\
+             clone_url_template=git@example.com:repo.git\n",
+        )?;
+
+        let config = Config::load_from(temp.path().to_path_buf())?;
+
+        let values = config.config_values.read().unwrap();
+        assert_eq!(values.http_timeout, None);
+        assert_eq!(values.pull_name_source, PullNameSource::default());
+        assert!(!values.use_upstream);
+        assert_eq!(values.github_api, GitHubApiBackend::default());
+        assert!(!values.github_shorthand);
+        assert_eq!(values.clone_url_template, None);
+        // Valid settings next to the bad ones still load
+        assert_eq!(values.default_remote, "upstream");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_unversioned_config_file() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        std::env::set_var("XDG_CONFIG_HOME", temp.path());
+        let config_file = temp.child("git-repo-name").child("config");
+        std::fs::create_dir_all(temp.path().join("git-repo-name"))?;
+        config_file.write_str("default_remote=upstream\n\n[github]\ntoken=legacy-token\n")?;
+
+        let config = Config::new()?;
+        env::remove_var("XDG_CONFIG_HOME");
+
+        assert_eq!(config.get_remote()?, "upstream");
+        assert_eq!(config.get_github_token()?, "legacy-token");
+        let ini = Ini::load_from_file(config_file.path())?;
+        assert_eq!(
+            ini.get_from(Some(META_SECTION), "version"),
+            Some(CONFIG_VERSION.to_string().as_str())
+        );
+        assert_eq!(ini.get_from(Some("github"), "token"), Some("legacy-token"));
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::metadata(config_file.path())?.permissions().mode() & 0o777,
+            0o600
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_replaces_config_file_atomically() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        config.write_to_disk()?;
+        let config_file = config.get_config_file_path();
+        #[cfg(unix)]
+        let original_inode = std::os::unix::fs::MetadataExt::ino(&std::fs::metadata(&config_file)?);
+
+        config.set_default_remote("upstream")?;
+
+        // The old file was replaced rather than rewritten in place
+        let ini = Ini::load_from_file(&config_file)?;
+        assert_eq!(
+            ini.get_from(None::<String>, "default_remote"),
+            Some("upstream")
+        );
+        assert_eq!(
+            ini.get_from(Some(META_SECTION), "version"),
+            Some(CONFIG_VERSION.to_string().as_str())
+        );
+        // No temporary file is left behind next to it
+        let entries: Vec<_> = std::fs::read_dir(temp.path())?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(entries, vec![config_file.file_name().unwrap().to_owned()]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let replaced = std::fs::metadata(&config_file)?;
+            assert_ne!(replaced.ino(), original_inode);
+            assert_eq!(replaced.permissions().mode() & 0o777, 0o600);
+        }
+
         Ok(())
     }
 
@@ -294,12 +1475,9 @@ mod tests {
             .join("git-repo-name");
         std::env::set_var("XDG_CONFIG_HOME", temp.path());
         let config = Config {
-            config_dir: config_dir,
-            config_values: RwLock::new(ConfigValues {
-                github_token: None,
-                default_remote: "origin".to_string(),
-                remote: None,
-            }),
+            config_dir,
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
         };
 
         config.write_to_disk()?;
@@ -318,4 +1496,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_pull_name_source() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        config.write_to_disk()?;
+        assert_eq!(config.get_pull_name_source()?, PullNameSource::Api);
+
+        config.set_pull_name_source(PullNameSource::Url)?;
+
+        let new_config = Config {
+            config_dir: temp.path().to_path_buf(),
+            config_values: Arc::new(RwLock::new(ConfigValues::default())),
+            github_host: None,
+        };
+        let ini = Ini::load_from_file(temp.child("config"))?;
+        new_config.load_from_ini(&ini)?;
+        assert_eq!(new_config.get_pull_name_source()?, PullNameSource::Url);
+
+        assert!("display".parse::<PullNameSource>().is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,260 @@
+use crate::{
+    config, git,
+    remotes::{self, github::client},
+    types::Result,
+};
+use std::path::Path;
+
+/// The outcome of a single diagnostic check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    /// Whether a failure means the tool can't work, rather than just a warning.
+    pub critical: bool,
+    pub detail: String,
+    /// How to fix a failed check.
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: true,
+            critical: true,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: false,
+            critical: true,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn non_critical(mut self) -> Self {
+        self.critical = false;
+        self
+    }
+}
+
+/// Runs all diagnostics, prints a line per check and returns whether every
+/// critical check passed.
+pub fn doctor() -> Result<bool> {
+    let checks = run_checks();
+
+    for check in &checks {
+        let status = match (check.passed, check.critical) {
+            (true, _) => "ok",
+            (false, true) => "FAIL",
+            (false, false) => "warn",
+        };
+        println!("[{}] {}: {}", status, check.name, check.detail);
+        if let (false, Some(hint)) = (check.passed, &check.hint) {
+            println!("       hint: {}", hint);
+        }
+    }
+
+    Ok(checks.iter().all(|check| check.passed || !check.critical))
+}
+
+fn run_checks() -> Vec<Check> {
+    let mut checks = vec![check_config_file(&config::current().get_config_file_path())];
+
+    let token_check = check_token_present();
+    let has_token = token_check.passed;
+    checks.push(token_check);
+    if has_token {
+        checks.push(check_token_authenticates());
+    }
+
+    let repo_check = check_git_repo();
+    let in_repo = repo_check.passed;
+    checks.push(repo_check);
+    if in_repo {
+        checks.push(check_remote());
+    }
+
+    checks
+}
+
+fn check_config_file(path: &Path) -> Check {
+    const NAME: &str = "Config file";
+
+    if let Err(e) = std::fs::read_to_string(path) {
+        return Check::fail(
+            NAME,
+            format!("cannot read {}: {}", path.display(), e),
+            "Run 'git-repo-name config default-remote origin' to create it",
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.permissions().mode() & 0o777,
+            Err(e) => {
+                return Check::fail(
+                    NAME,
+                    format!("cannot stat {}: {}", path.display(), e),
+                    "Check that the config directory is accessible",
+                )
+            }
+        };
+        if mode != 0o600 {
+            return Check::fail(
+                NAME,
+                format!("{} has permissions {:o}", path.display(), mode),
+                format!(
+                    "Run 'chmod 600 {}' so the token isn't readable by others",
+                    path.display()
+                ),
+            )
+            .non_critical();
+        }
+    }
+
+    Check::pass(NAME, path.display().to_string())
+}
+
+fn check_token_present() -> Check {
+    const NAME: &str = "GitHub token";
+
+    match config::current().get_github_token() {
+        Ok(token) if !token.is_empty() => Check::pass(NAME, "configured"),
+        Ok(_) => Check::fail(
+            NAME,
+            "not configured (only public GitHub repositories will work)",
+            "Run 'git-repo-name config github-token YOUR_TOKEN'",
+        )
+        .non_critical(),
+        Err(e) => Check::fail(
+            NAME,
+            format!("not available: {}", e),
+            "Run 'git-repo-name config github-token YOUR_TOKEN'",
+        )
+        .non_critical(),
+    }
+}
+
+fn check_token_authenticates() -> Check {
+    const NAME: &str = "GitHub authentication";
+
+    match client::get_authenticated_user() {
+        Ok(user) => Check::pass(NAME, format!("authenticated as {}", user.login)),
+        Err(e) => Check::fail(
+            NAME,
+            e.to_string(),
+            "Create a new token and run 'git-repo-name config github-token YOUR_TOKEN'",
+        ),
+    }
+}
+
+fn check_git_repo() -> Check {
+    const NAME: &str = "Git repository";
+
+    match git::get_current_repo() {
+        Ok(repo) => Check::pass(
+            NAME,
+            repo.workdir()
+                .unwrap_or_else(|| repo.path())
+                .display()
+                .to_string(),
+        ),
+        Err(e) => Check::fail(
+            NAME,
+            e.to_string(),
+            "Run git-repo-name inside a git repository",
+        ),
+    }
+}
+
+fn check_remote() -> Check {
+    const NAME: &str = "Remote";
+
+    let remote_url = match git::get_current_repo().and_then(|repo| git::get_remote_url(&repo)) {
+        Ok(remote_url) => remote_url,
+        Err(e) => {
+            return Check::fail(
+                NAME,
+                e.to_string(),
+                "Add a remote with 'git remote add origin URL' or pick one with --remote",
+            )
+        }
+    };
+
+    match remotes::validate_remote_url(&remote_url) {
+        Ok(_) => Check::pass(NAME, remote_url),
+        Err(e) => Check::fail(
+            NAME,
+            e.to_string(),
+            "Point the remote at a GitHub URL or an existing repository path",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn test_check_config_file() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let config_file = temp.child("config");
+
+        assert!(!check_config_file(config_file.path()).passed);
+
+        config_file.write_str("default_remote=origin\n")?;
+        crate::utils::fs::set_secure_permissions(config_file.path())?;
+        assert!(check_config_file(config_file.path()).passed);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(config_file.path(), std::fs::Permissions::from_mode(0o644))?;
+            let check = check_config_file(config_file.path());
+            assert!(!check.passed && !check.critical);
+            assert!(check.detail.contains("permissions 644"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_git_repo_and_remote() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let not_a_repo = temp.child("plain-dir");
+        not_a_repo.create_dir_all()?;
+        std::env::set_current_dir(not_a_repo.path())?;
+        assert!(!check_git_repo().passed);
+
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "test-repo")?;
+        std::env::set_current_dir(&repo_dir)?;
+        assert!(check_git_repo().passed);
+        assert!(!check_remote().passed);
+
+        repo.remote("origin", "git@github.com:owner/test-repo.git")?;
+        let check = check_remote();
+        assert!(
+            check.passed,
+            "Expected remote check to pass: {}",
+            check.detail
+        );
+
+        Ok(())
+    }
+}
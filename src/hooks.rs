@@ -0,0 +1,69 @@
+use crate::{
+    config,
+    progress::{ProgressSink, SyncOutcome},
+    types::{Error, Result},
+};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Runs the configured `post-rename-hook`, if any, after the working directory
+/// moved from `old_path` to `new_path`. In dry-run mode the command is only
+/// reported to `sink`.
+pub fn run_post_rename_hook(
+    old_path: &Path,
+    new_path: &Path,
+    repo_name: &str,
+    dry_run: bool,
+    sink: &dyn ProgressSink,
+) -> Result<()> {
+    let Some(hook) = config::current().get_post_rename_hook() else {
+        return Ok(());
+    };
+
+    sink.on_change(&SyncOutcome::Hook {
+        command: hook.clone(),
+        dry_run,
+    });
+    if dry_run {
+        return Ok(());
+    }
+
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+
+    // Working directories come with a trailing slash; drop it for the hook
+    let old_path: PathBuf = old_path.components().collect();
+    let status = command
+        .arg(&hook)
+        .current_dir(new_path)
+        .env("GRN_OLD_PATH", &old_path)
+        .env("GRN_NEW_PATH", new_path)
+        .env("GRN_REPO_NAME", repo_name)
+        // Keep stdout for the progress output, which may be JSON
+        .stdout(Stdio::from(std::io::stderr()))
+        .status()
+        .map_err(|e| {
+            Error::Other(anyhow::anyhow!(
+                "Failed to run post-rename hook '{}': {}",
+                hook,
+                e
+            ))
+        })?;
+
+    if !status.success() {
+        return Err(Error::Other(anyhow::anyhow!(
+            "Post-rename hook '{}' failed ({})",
+            hook,
+            status
+        )));
+    }
+
+    Ok(())
+}
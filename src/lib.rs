@@ -1,73 +1,443 @@
+pub mod audit;
+pub mod batch;
+pub mod completion;
 pub mod config;
+pub mod doctor;
 pub mod git;
+pub mod hooks;
+pub mod progress;
 pub mod types;
 pub mod utils {
+    pub mod color;
     pub mod fs;
+    pub mod prompt;
+    pub mod shell;
 }
-pub mod remotes {
-    pub mod file {
-        pub mod operations;
-        pub mod url;
-    }
-    pub mod github {
-        pub mod client;
-        pub mod operations;
-        pub mod url;
-    }
-}
+pub mod remotes;
 #[cfg(test)]
 pub(crate) mod test_helpers;
 use crate::{
-    remotes::{file, github},
-    types::Result,
+    config::{Config, PullNameSource, CONFIG},
+    progress::{ProgressSink, SyncOutcome},
+    types::{
+        AlignOptions, CloneOptions, Error, FetchOptions, PullOptions, PushOptions, RemoteKind,
+        RepoName, Result,
+    },
+    utils::shell,
 };
-use std::path::Path;
+use git2::Repository;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use remotes::github::client::{self, DeviceTokenPoll};
+use std::io::Write;
+use std::time::{Duration, Instant};
 
-pub fn pull(dry_run: bool) -> Result<()> {
-    let repo = git::get_current_repo()?;
-    let remote_url = git::get_remote_url(&repo)?;
+/// Reports each change to `sink`. Returns whether anything was (or, in dry-run
+/// mode, would be) changed.
+pub fn pull(options: &PullOptions, sink: &dyn ProgressSink) -> Result<bool> {
+    pull_with(&CONFIG, options, sink)
+}
 
-    if github::url::is_github_url(&remote_url) {
-        github::operations::pull_from_github_remote(&repo, &remote_url, dry_run)
+/// Like [`pull`], but reads settings (token, remote, `-C` path, ...) from `config`
+/// instead of the process-wide [`CONFIG`].
+pub fn pull_with(config: &Config, options: &PullOptions, sink: &dyn ProgressSink) -> Result<bool> {
+    config::with_config(config, || {
+        pull_repo(&git::get_current_repo()?, options, sink)
+    })
+}
+
+/// Pulls a single repository. Returns whether anything was (or, in dry-run mode,
+/// would be) changed.
+pub(crate) fn pull_repo(
+    repo: &Repository,
+    options: &PullOptions,
+    sink: &dyn ProgressSink,
+) -> Result<bool> {
+    let remote_url = match (&options.remote_url, options.offline) {
+        // The path doesn't have to exist offline, so there's nothing to validate
+        (Some(url), true) => url.clone(),
+        (Some(url), false) => remotes::validate_remote_url(url)?,
+        (None, _) => git::get_selected_remote_url(repo, options.remote.as_deref())?,
+    };
+
+    // The created remote is the configured one even when the repository's only
+    // remote has another name, so the rest of the pull must use it explicitly
+    let with_created_remote;
+    let options = if options.create_remote && options.remote_url.is_some() {
+        let remote_name = match &options.remote {
+            Some(remote) => remote.clone(),
+            None => config::current().get_remote()?,
+        };
+        if repo.find_remote(&remote_name).is_err() {
+            if !options.dry_run {
+                git::create_remote(repo, &remote_name, &remote_url)?;
+            }
+            sink.on_change(&SyncOutcome::RemoteAdded {
+                remote: remote_name.clone(),
+                url: remote_url.clone(),
+                dry_run: options.dry_run,
+            });
+        }
+        with_created_remote = PullOptions {
+            remote: Some(remote_name),
+            ..options.clone()
+        };
+        &with_created_remote
     } else {
-        file::operations::pull_from_file_remote(&repo, &remote_url, dry_run)
+        options
+    };
+
+    let provider = remotes::provider_for_url(&remote_url, options.name_source, options.offline);
+
+    if options.print_commands {
+        let plan = remotes::plan_pull(
+            provider.as_ref(),
+            repo,
+            &remote_url,
+            options.remote.as_deref(),
+        )?
+        .limit_to(options);
+        for command in plan.commands() {
+            sink.on_change(&SyncOutcome::Command { command });
+        }
+        return Ok(plan.has_changes());
     }
+
+    remotes::pull(provider.as_ref(), repo, &remote_url, options, sink)
 }
 
-pub fn push(dry_run: bool) -> Result<()> {
+/// Returns whether anything was (or, in dry-run mode, would be) changed.
+pub fn align(options: &AlignOptions) -> Result<bool> {
     let repo = git::get_current_repo()?;
-    let remote_url = git::get_remote_url(&repo)?;
+    let remote_url = resolve_remote_url(&repo, options.remote_url.as_deref())?;
+
+    let provider = remotes::provider_for_url(&remote_url, None, false);
+    remotes::align(
+        provider.as_ref(),
+        &repo,
+        &remote_url,
+        options,
+        &mut std::io::stdin().lock(),
+    )
+}
+
+pub fn push(options: &PushOptions) -> Result<()> {
+    push_with(&CONFIG, options)
+}
+
+/// Like [`push`], but reads settings from `config` instead of [`CONFIG`].
+pub fn push_with(config: &Config, options: &PushOptions) -> Result<()> {
+    config::with_config(config, || {
+        let repo = git::get_current_repo()?;
+        let remote_url = resolve_remote_url(&repo, options.remote_url.as_deref())?;
+
+        let provider = remotes::provider_for_url(&remote_url, None, false);
+        remotes::push(provider.as_ref(), &repo, &remote_url, options)
+    })
+}
+
+/// Clones the repository into a directory named after its canonical name, below
+/// the `-C` path or the current directory. Returns the (would-be) clone path.
+pub fn clone(options: &CloneOptions) -> Result<std::path::PathBuf> {
+    let url = remotes::validate_remote_url(&options.url)?;
+    let provider = remotes::provider_for_url(&url, Some(PullNameSource::Api), false);
+    let resolved = provider.resolve_repo_name(&url)?;
+    let clone_url = provider.format_remote_url(&url, &resolved)?;
+
+    let parent = match config::current().get_repo_path() {
+        Some(path) => path,
+        None => std::env::current_dir().map_err(|_| Error::StaleWorkingDirectory)?,
+    };
+    let target = parent.join(&resolved.name);
+    if target.exists() {
+        return Err(Error::Fs(format!(
+            "Target path '{}' already exists",
+            target.display()
+        )));
+    }
+
+    if options.dry_run {
+        println!(
+            "Would clone '{}' into '{}'",
+            git::redact_credentials(&clone_url),
+            target.display()
+        );
+        return Ok(target);
+    }
+
+    println!(
+        "Cloning '{}' into '{}'...",
+        git::redact_credentials(&clone_url),
+        target.display()
+    );
+    Repository::clone(&clone_url, &target).map_err(|e| Error::Other(e.into()))?;
+
+    Ok(target)
+}
+
+/// Resolves the canonical repository name from the remote without printing anything.
+pub fn resolve_repo_name(options: &FetchOptions) -> Result<RepoName> {
+    // An explicit URL doesn't need a repository to look it up
+    let remote_url = match &options.remote_url {
+        Some(url) => remotes::validate_remote_url(url)?,
+        None => git::get_remote_url(&git::get_current_repo()?)?,
+    };
+
+    resolve_remote(&remote_url)
+}
+
+/// Looks up the repository name a remote URL points to.
+fn resolve_remote(remote_url: &str) -> Result<RepoName> {
+    let provider = remotes::provider_for_url(remote_url, Some(PullNameSource::Api), false);
+    let resolved = provider.resolve_repo_name(remote_url)?;
+    let owner = match provider.kind() {
+        RemoteKind::File => None,
+        _ => resolved
+            .full_name
+            .rsplit_once('/')
+            .map(|(owner, _)| owner.to_string()),
+    };
+
+    Ok(RepoName {
+        name: resolved.name,
+        full_name: resolved.full_name,
+        owner,
+        clone_url: resolved.location,
+        source: provider.kind(),
+        moved_from: resolved.moved_from,
+    })
+}
 
-    if github::url::is_github_url(&remote_url) {
-        github::operations::push_to_github_remote(&repo, &remote_url, dry_run)
+/// Authorizes the configured OAuth app through GitHub's device flow and stores
+/// the resulting token, so it doesn't have to be created and pasted by hand.
+pub fn login() -> Result<()> {
+    let client_id = config::current().get_oauth_client_id().ok_or_else(|| {
+        Error::Config(
+            "No OAuth client ID configured. Set one with 'git-repo-name config oauth-client-id CLIENT_ID'".into(),
+        )
+    })?;
+
+    let code = client::request_device_code(&client_id)?;
+    println!("First copy your one-time code: {}", code.user_code);
+    println!("Then open {} and enter it", code.verification_uri);
+
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = Duration::from_secs(code.interval);
+    loop {
+        std::thread::sleep(interval);
+        if Instant::now() > deadline {
+            return Err(Error::GitHubApi(
+                "The code expired before it was entered. Run 'git-repo-name login' again".into(),
+            ));
+        }
+
+        match client::poll_device_token(&client_id, &code.device_code)? {
+            DeviceTokenPoll::Pending => {}
+            // The spec asks clients to wait 5 more seconds when told to slow down
+            DeviceTokenPoll::SlowDown(seconds) => {
+                interval = seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(interval + Duration::from_secs(5));
+            }
+            DeviceTokenPoll::Token(token) => {
+                config::current().set_github_token(&token)?;
+                println!("Logged in. GitHub token configured successfully");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The remote URL `pull` would set for the configured remote (or `remote_url`):
+/// the reformatted URL for GitHub and forge remotes, the canonical `file://` URL
+/// for file remotes. Neither git config nor the filesystem is changed.
+pub fn canonical_remote_url(remote_url: Option<&str>) -> Result<String> {
+    let remote_url = match remote_url {
+        Some(url) => remotes::validate_remote_url(url)?,
+        None => git::get_remote_url(&git::get_current_repo()?)?,
+    };
+
+    let provider = remotes::provider_for_url(&remote_url, None, false);
+    let resolved = provider.resolve_repo_name(&remote_url)?;
+    match provider.kind() {
+        RemoteKind::File => Ok(resolved.location),
+        _ => provider.format_remote_url(&remote_url, &resolved),
+    }
+}
+
+/// Describes the lookup `fetch` would make, without any network access. Local
+/// file remotes are resolved lexically, so the path doesn't have to exist.
+pub fn describe_fetch(options: &FetchOptions) -> Result<String> {
+    let remote_url = match &options.remote_url {
+        Some(url) => url.clone(),
+        None => git::get_remote_url(&git::get_current_repo()?)?,
+    };
+
+    match remotes::provider_for_url(&remote_url, None, true).kind() {
+        RemoteKind::GitHub => {
+            let (_, owner, repo) = remotes::github::url::parse_github_url(&remote_url)?;
+            Ok(format!(
+                "Would query {}/{} at {}",
+                owner,
+                repo,
+                remotes::github::provider::with_host_config(&remote_url, || {
+                    remotes::github::client::repos_url(&owner, &repo)
+                })
+            ))
+        }
+        RemoteKind::Gitea => {
+            let forge = remotes::configured_forge(&remote_url)
+                .ok_or_else(|| Error::InvalidRemoteUrl(remote_url.clone()))?;
+            let (_, owner, repo) = remotes::gitea::url::parse_forge_url(&remote_url)
+                .ok_or_else(|| Error::InvalidRemoteUrl(remote_url.clone()))?;
+            Ok(format!(
+                "Would query {}/{} at {}",
+                owner,
+                repo,
+                remotes::gitea::client::repos_url(&forge, &owner, &repo)
+            ))
+        }
+        // A path on another host is taken as written
+        RemoteKind::File if remotes::file::url::ssh_remote_path(&remote_url).is_some() => {
+            Ok(format!("Would resolve {}", remote_url))
+        }
+        RemoteKind::File => {
+            let path = utils::fs::resolve_absolute_path(std::path::Path::new(&remote_url))?;
+            Ok(format!("Would resolve {}", path))
+        }
+    }
+}
+
+/// Looks up a GitHub repository named after the local directory under the
+/// authenticated user and prints whether it exists. Returns its clone URL if so.
+pub fn fetch_by_directory() -> Result<Option<String>> {
+    let name = git::get_local_directory_name(&git::get_current_repo()?)?;
+    let owner = client::get_authenticated_user()?.login;
+
+    match client::find_repo_info(&owner, &name)? {
+        Some(repo) => {
+            println!("{} exists ({})", repo.full_name, repo.clone_url);
+            Ok(Some(repo.clone_url))
+        }
+        None => {
+            println!("{}/{} does not exist", owner, name);
+            Ok(None)
+        }
+    }
+}
+
+/// Resolves the repository name and prints it in the requested format.
+pub fn fetch_repo_name(options: &FetchOptions) -> Result<RepoName> {
+    let repo_name = resolve_repo_name(options)?;
+
+    if options.no_newline {
+        print!("{}", repo_name.name);
+        std::io::stdout().flush()?;
+    } else if let Some(template) = &options.format {
+        println!("{}", format_repo_name(template, &repo_name)?);
+    } else if options.emit_env {
+        println!(
+            "GRN_REPO_NAME={}\nGRN_REPO_URL={}",
+            shell::quote(&repo_name.name),
+            shell::quote(&repo_name.clone_url)
+        );
     } else {
-        file::operations::push_to_file_remote(&repo, &remote_url, dry_run)
+        match &repo_name.moved_from {
+            Some(moved_from) => println!(
+                "{} ({}) (renamed from {})",
+                repo_name.name, repo_name.clone_url, moved_from
+            ),
+            None => println!("{} ({})", repo_name.name, repo_name.clone_url),
+        }
     }
+
+    Ok(repo_name)
 }
 
-pub fn fetch_repo_name() -> Result<String> {
-    let repo = git::get_current_repo()?;
-    let remote_url = git::get_remote_url(&repo)?;
-    let result;
+/// A `{placeholder}` in a `fetch --format` template.
+static FORMAT_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(\w+)\}").unwrap());
 
-    if github::url::is_github_url(&remote_url) {
-        let (owner, repo_name) = github::url::parse_github_url(&remote_url)?;
-        let repo_info = github::client::get_repo_info(&owner, &repo_name)?;
-        result = format!("{} ({})", repo_info.name, repo_info.clone_url);
+/// Fills in a `fetch --format` template. Every remote supports `{name}` and
+/// `{clone_url}`; GitHub and Gitea remotes add `{owner}` and `{full_name}`, and
+/// file remotes add `{path}`.
+pub fn format_repo_name(template: &str, repo_name: &RepoName) -> Result<String> {
+    let mut fields = vec![
+        ("name", repo_name.name.clone()),
+        ("clone_url", repo_name.clone_url.clone()),
+    ];
+    if repo_name.source == RemoteKind::File {
+        let path = repo_name.clone_url.trim_start_matches("file://");
+        fields.push(("path", path.to_string()));
     } else {
-        let canonical_path = utils::fs::resolve_canonical_path(Path::new(&remote_url))?;
-        let name = git::extract_repo_name_from_path(&canonical_path)?;
-        result = format!("{} ({})", name, canonical_path);
+        fields.push(("full_name", repo_name.full_name.clone()));
+        fields.push(("owner", repo_name.owner.clone().unwrap_or_default()));
+    }
+
+    if let Some(unknown) = FORMAT_PLACEHOLDER
+        .captures_iter(template)
+        .map(|c| c[1].to_string())
+        .find(|key| fields.iter().all(|(field, _)| field != key))
+    {
+        let valid: Vec<_> = fields
+            .iter()
+            .map(|(field, _)| format!("{{{}}}", field))
+            .collect();
+        return Err(Error::Config(format!(
+            "Unknown format placeholder {{{}}}. Valid placeholders: {}",
+            unknown,
+            valid.join(", ")
+        )));
+    }
+
+    Ok(FORMAT_PLACEHOLDER
+        .replace_all(template, |c: &regex::Captures| {
+            fields
+                .iter()
+                .find(|(field, _)| *field == &c[1])
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default()
+        })
+        .into_owned())
+}
+
+/// Resolves every configured remote of the current repository and prints a line
+/// per remote. A remote that fails to resolve is reported without stopping the rest.
+pub fn fetch_all_remotes() -> Result<Vec<(String, Result<RepoName>)>> {
+    let repo = git::get_current_repo()?;
+    let remote_names = repo.remotes().map_err(|e| Error::Other(e.into()))?;
+
+    let results: Vec<_> = remote_names
+        .iter()
+        .flatten()
+        .map(|remote| {
+            let result = git::get_selected_remote_url(&repo, Some(remote))
+                .and_then(|url| resolve_remote(&url));
+            match &result {
+                Ok(repo_name) => {
+                    println!("{}: {} ({})", remote, repo_name.name, repo_name.clone_url)
+                }
+                Err(e) => eprintln!("{}: {}", remote, e),
+            }
+            (remote.to_string(), result)
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Returns the `--remote-url` override when given, otherwise the configured remote's URL.
+fn resolve_remote_url(repo: &Repository, override_url: Option<&str>) -> Result<String> {
+    match override_url {
+        Some(url) => remotes::validate_remote_url(url),
+        None => git::get_remote_url(repo),
     }
-    println!("{}", result);
-    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_helpers;
-    use git2::Repository;
+    use crate::types::{Error, RemoteKind};
+    use crate::{progress::TextSink, test_helpers};
 
     #[test]
     fn test_fetch_repo_name_filesystem() -> anyhow::Result<()> {
@@ -97,8 +467,18 @@ mod tests {
                     .canonicalize()?
                     .display()
             );
-            let name = fetch_repo_name()?;
-            assert_eq!(name, format!("upstream_repo ({})", expected_url));
+            let repo_name = fetch_repo_name(&FetchOptions::default())?;
+            assert_eq!(
+                repo_name,
+                RepoName {
+                    name: "upstream_repo".to_string(),
+                    full_name: "upstream_repo".to_string(),
+                    owner: None,
+                    clone_url: expected_url,
+                    source: RemoteKind::File,
+                    moved_from: None,
+                }
+            );
 
             std::env::set_current_dir(&original_dir)?;
             std::fs::remove_dir_all(&main_repo_dir)?;
@@ -129,11 +509,13 @@ mod tests {
             std::env::set_current_dir(&main_repo_dir)?;
 
             repo.remote("origin", url)?;
-            let name = fetch_repo_name()?;
+            let repo_name = fetch_repo_name(&FetchOptions::default())?;
+            assert_eq!(repo_name.name, "upstream-repo");
             assert_eq!(
-                name,
-                "upstream-repo (https://github.com/owner/upstream-repo.git)"
+                repo_name.clone_url,
+                "https://github.com/owner/upstream-repo.git"
             );
+            assert_eq!(repo_name.source, RemoteKind::GitHub);
 
             std::env::set_current_dir(&original_dir)?;
             std::fs::remove_dir_all(&main_repo_dir)?;
@@ -144,4 +526,696 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_clone_file_remote() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let bare_repo_path = test_helpers::create_bare_repo(&temp, "upstream-repo.git")?;
+        let work_dir = temp.path().join("work");
+        std::fs::create_dir(&work_dir)?;
+        std::env::set_current_dir(&work_dir)?;
+        let options = CloneOptions {
+            url: bare_repo_path.display().to_string(),
+            dry_run: true,
+        };
+
+        let (output, target) = test_helpers::capture_stdout(|| clone(&options))?;
+        assert!(output.starts_with("Would clone"), "{}", output);
+        assert!(!target.exists());
+
+        let (_, target) = test_helpers::capture_stdout(|| {
+            clone(&CloneOptions {
+                dry_run: false,
+                ..options.clone()
+            })
+        })?;
+        assert_eq!(target.file_name(), Some("upstream-repo".as_ref()));
+        let cloned = Repository::open(&target)?;
+        assert_eq!(
+            git::get_remote_url(&cloned)?,
+            bare_repo_path.canonicalize()?.display().to_string()
+        );
+
+        // An existing directory is never cloned into
+        assert!(matches!(clone(&options), Err(Error::Fs(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_repo_name_format() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "new-owner", "old-name", "new-name");
+
+        for (template, expected) in [
+            ("{owner}/{name}", "new-owner/new-name"),
+            ("{clone_url}", "https://github.com/new-owner/new-name.git"),
+            ("repo={full_name}", "repo=new-owner/new-name"),
+        ] {
+            let (output, _) = test_helpers::capture_stdout(|| {
+                fetch_repo_name(&FetchOptions {
+                    remote_url: Some("https://github.com/owner/old-name.git".into()),
+                    format: Some(template.into()),
+                    ..Default::default()
+                })
+            })?;
+            assert_eq!(output, format!("{}\n", expected));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_repo_name_uses_resolved_owner() {
+        // A clone-url-template mirror whose path doesn't end in owner/name
+        let repo_name = RepoName {
+            name: "widget".into(),
+            full_name: "acme/widget".into(),
+            owner: Some("acme".into()),
+            clone_url: "https://mirror.acme.com/git/widget.git".into(),
+            source: RemoteKind::GitHub,
+            moved_from: None,
+        };
+        assert_eq!(
+            format_repo_name("{owner} {full_name}", &repo_name).unwrap(),
+            "acme acme/widget"
+        );
+    }
+
+    #[test]
+    fn test_format_repo_name_file_remote() {
+        let repo_name = RepoName {
+            name: "repo".into(),
+            full_name: "repo".into(),
+            owner: None,
+            clone_url: "file:///srv/git/repo.git".into(),
+            source: RemoteKind::File,
+            moved_from: None,
+        };
+        assert_eq!(
+            format_repo_name("{name} at {path}", &repo_name).unwrap(),
+            "repo at /srv/git/repo.git"
+        );
+        assert_eq!(
+            format_repo_name("{owner}/{name}", &repo_name)
+                .unwrap_err()
+                .to_string(),
+            "Error: Unknown format placeholder {owner}. Valid placeholders: {name}, {clone_url}, {path}"
+        );
+    }
+
+    #[test]
+    fn test_fetch_all_remotes() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _fork = test_helpers::mock_github_existing_repo(&mut server, "fork-owner", "fork");
+        let _upstream = server
+            .mock("GET", "/repos/owner/old-name")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "new-name",
+                    "full_name": "owner/new-name",
+                    "clone_url": "https://github.com/owner/new-name.git"
+                })
+                .to_string(),
+            )
+            .create();
+        // `owner/missing` is left unmocked, so looking it up fails
+
+        let (main_repo_dir, repo) = test_helpers::create_main_repo(&temp, "main-repo")?;
+        repo.remote("origin", "https://github.com/fork-owner/fork.git")?;
+        repo.remote("upstream", "https://github.com/owner/old-name.git")?;
+        repo.remote("stale", "https://github.com/owner/missing.git")?;
+        std::env::set_current_dir(&main_repo_dir)?;
+
+        let (output, results) = test_helpers::capture_stdout(fetch_all_remotes)?;
+        assert_eq!(
+            output,
+            "origin: fork (https://github.com/fork-owner/fork.git)\n\
+             upstream: new-name (https://github.com/owner/new-name.git)\n"
+        );
+        let failed: Vec<_> = results
+            .iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(remote, _)| remote.as_str())
+            .collect();
+        assert_eq!(failed, ["stale"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_repo_name_renamed() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+
+        let (output, repo_name) = test_helpers::capture_stdout(|| {
+            fetch_repo_name(&FetchOptions {
+                remote_url: Some("https://github.com/owner/old-name.git".into()),
+                ..Default::default()
+            })
+        })?;
+        assert_eq!(repo_name.moved_from.as_deref(), Some("owner/old-name"));
+        assert_eq!(
+            output.trim_end(),
+            "new-name (https://github.com/owner/new-name.git) (renamed from owner/old-name)"
+        );
+        std::env::remove_var("GITHUB_API_BASE_URL");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_remote_url() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let bare_repo = test_helpers::create_bare_repo(&temp, "upstream.git")?;
+        let (main_repo_dir, repo) = test_helpers::create_main_repo(&temp, "main-repo")?;
+        repo.remote("origin", "../upstream.git")?;
+        std::env::set_current_dir(&main_repo_dir)?;
+
+        assert_eq!(
+            canonical_remote_url(None)?,
+            utils::fs::resolve_canonical_path(&bare_repo)?
+        );
+        assert_eq!(repo.find_remote("origin")?.url(), Some("../upstream.git"));
+
+        repo.remote_set_url("origin", "file://../upstream.git")?;
+        assert_eq!(
+            canonical_remote_url(None)?,
+            format!("file://{}", bare_repo.canonicalize()?.display())
+        );
+        assert_eq!(
+            repo.find_remote("origin")?.url(),
+            Some("file://../upstream.git")
+        );
+
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        assert_eq!(
+            canonical_remote_url(Some("git@github.com:owner/old-name.git"))?,
+            "git@github.com:owner/new-name.git"
+        );
+        std::env::remove_var("GITHUB_API_BASE_URL");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_repo_name_uses_token_of_remote_host() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        std::env::remove_var("GITHUB_API_BASE_URL");
+        std::env::remove_var("GITHUB_TOKEN");
+        let mut acme = mockito::Server::new();
+        let mut beta = mockito::Server::new();
+        let mock_repo = |server: &mut mockito::ServerGuard, token: &str, name: &str| {
+            server
+                .mock("GET", format!("/repos/owner/{}", name).as_str())
+                .match_header("authorization", format!("token {}", token).as_str())
+                .with_body(
+                    serde_json::json!({
+                        "name": name,
+                        "full_name": format!("owner/{}", name),
+                        "clone_url": format!("https://example.com/owner/{}.git", name)
+                    })
+                    .to_string(),
+                )
+                .expect(1)
+                .create()
+        };
+        let acme_repo = mock_repo(&mut acme, "acme-token", "acme-repo");
+        let beta_repo = mock_repo(&mut beta, "beta-token", "beta-repo");
+
+        let config_dir = temp.path().join("config");
+        std::fs::create_dir_all(&config_dir)?;
+        std::fs::write(
+            config_dir.join("config"),
+            format!(
+                "[meta]\nversion=1\n\n\
+                 [github]\ntoken=default-token\n\n\
+                 [github \"github.acme.internal\"]\ntoken=acme-token\napi_base_url={}\n\n\
+                 [github \"github.beta.internal\"]\ntoken=beta-token\napi_base_url={}\n",
+                acme.url(),
+                beta.url()
+            ),
+        )?;
+        let config = Config::load_from(config_dir)?;
+
+        for (host, name) in [
+            ("github.acme.internal", "acme-repo"),
+            ("github.beta.internal", "beta-repo"),
+        ] {
+            let options = FetchOptions {
+                remote_url: Some(format!("git@{}:owner/{}.git", host, name)),
+                ..Default::default()
+            };
+            let repo_name = config::with_config(&config, || resolve_repo_name(&options))?;
+            assert_eq!(repo_name.name, name);
+        }
+
+        acme_repo.assert();
+        beta_repo.assert();
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_by_directory() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        server
+            .mock("GET", "/user")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"login": "me"}"#)
+            .create();
+        server
+            .mock("GET", "/repos/me/existing-repo")
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "existing-repo",
+                    "full_name": "me/existing-repo",
+                    "clone_url": "https://github.com/me/existing-repo.git"
+                })
+                .to_string(),
+            )
+            .create();
+        server
+            .mock("GET", "/repos/me/new-repo")
+            .with_status(404)
+            .with_body(r#"{"message": "Not Found"}"#)
+            .create();
+
+        let (existing_dir, _) = test_helpers::create_main_repo(&temp, "existing-repo")?;
+        std::env::set_current_dir(&existing_dir)?;
+        let (output, clone_url) = test_helpers::capture_stdout(fetch_by_directory)?;
+        assert_eq!(
+            clone_url.as_deref(),
+            Some("https://github.com/me/existing-repo.git")
+        );
+        assert_eq!(
+            output.trim_end(),
+            "me/existing-repo exists (https://github.com/me/existing-repo.git)"
+        );
+
+        let (new_dir, _) = test_helpers::create_main_repo(&temp, "new-repo")?;
+        std::env::set_current_dir(&new_dir)?;
+        let (output, clone_url) = test_helpers::capture_stdout(fetch_by_directory)?;
+        assert_eq!(clone_url, None);
+        assert_eq!(output.trim_end(), "me/new-repo does not exist");
+        std::env::remove_var("GITHUB_API_BASE_URL");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_repo_name_emit_env() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "test-repo", "test-repo");
+
+        let (main_repo_dir, repo) = test_helpers::create_main_repo(&temp, "main-repo")?;
+        std::env::set_current_dir(&main_repo_dir)?;
+        repo.remote("origin", "https://github.com/owner/test-repo.git")?;
+
+        let (output, _) = test_helpers::capture_stdout(|| {
+            fetch_repo_name(&FetchOptions {
+                emit_env: true,
+                ..Default::default()
+            })
+        })?;
+        assert_eq!(
+            output.trim_end(),
+            "GRN_REPO_NAME=test-repo\nGRN_REPO_URL=https://github.com/owner/test-repo.git"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_repo_name_remote_url_override() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "test-repo", "test-repo");
+
+        // No remote is configured, so only the override can be used
+        let (main_repo_dir, _repo) = test_helpers::create_main_repo(&temp, "main-repo")?;
+        std::env::set_current_dir(&main_repo_dir)?;
+
+        let (output, repo_name) = test_helpers::capture_stdout(|| {
+            fetch_repo_name(&FetchOptions {
+                remote_url: Some("git@github.com:owner/test-repo.git".to_string()),
+                ..Default::default()
+            })
+        })?;
+        assert_eq!(
+            output.trim_end(),
+            "test-repo (https://github.com/owner/test-repo.git)"
+        );
+        assert_eq!(repo_name.source, RemoteKind::GitHub);
+
+        let result = fetch_repo_name(&FetchOptions {
+            remote_url: Some("../no-such-repo.git".to_string()),
+            ..Default::default()
+        });
+        assert!(
+            matches!(result, Err(Error::InvalidRemoteUrl(_))),
+            "Expected invalid remote URL error"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_fetch_makes_no_requests() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let mock = server.mock("GET", mockito::Matcher::Any).expect(0).create();
+
+        let (main_repo_dir, repo) = test_helpers::create_main_repo(&temp, "main-repo")?;
+        repo.remote("origin", "git@github.com:owner/test-repo.git")?;
+        std::env::set_current_dir(&main_repo_dir)?;
+
+        let options = FetchOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            describe_fetch(&options)?,
+            format!(
+                "Would query owner/test-repo at {}/repos/owner/test-repo",
+                server.url()
+            )
+        );
+
+        // File remotes don't need to exist
+        let options = FetchOptions {
+            dry_run: true,
+            remote_url: Some("../missing/upstream.git".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe_fetch(&options)?,
+            format!(
+                "Would resolve file://{}",
+                temp.path().join("missing/upstream.git").display()
+            )
+        );
+
+        mock.assert();
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_fetch_dispatches_on_provider() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let config_dir = temp.path().join("config");
+        std::fs::create_dir_all(&config_dir)?;
+        std::fs::write(
+            config_dir.join("config"),
+            "[meta]\nversion=1\n\n\
+             [provider.mygitea]\nhost=git.example.com\n\
+             api_base=https://git.example.com/api/v1\nkind=gitea\n",
+        )?;
+        let config = Config::load_from(config_dir)?;
+
+        let describe = |url: &str| {
+            let options = FetchOptions {
+                dry_run: true,
+                remote_url: Some(url.to_string()),
+                ..Default::default()
+            };
+            config::with_config(&config, || describe_fetch(&options))
+        };
+
+        assert_eq!(
+            describe("git@git.example.com:owner/test-repo.git")?,
+            "Would query owner/test-repo at https://git.example.com/api/v1/repos/owner/test-repo"
+        );
+        // A file remote over SSH isn't resolved against the local filesystem
+        assert_eq!(
+            describe("backup.example.com:/srv/git/test-repo.git")?,
+            "Would resolve backup.example.com:/srv/git/test-repo.git"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_with_repo_path() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        repo.remote("origin", "git@github.com:owner/old-name.git")?;
+        // Run from outside the repository
+        std::env::set_current_dir(temp.path())?;
+
+        let config = Config::load_from(temp.path().join(".config/git-repo-name"))?;
+        config.set_repo_path(Some(repo_dir));
+        let (_, changed) = test_helpers::capture_stdout(|| {
+            pull_with(&config, &PullOptions::default(), &TextSink)
+        })?;
+        assert!(changed);
+        test_helpers::assert_directory_existence(&temp, "old-name", false)?;
+        let renamed = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(
+            git::get_remote_url(&renamed)?,
+            "git@github.com:owner/new-name.git"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_keeps_configured_url_spelling() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        repo.remote("origin", "https://github.com/owner/old-name.git")?;
+        repo.config()?
+            .set_str("url.git@github.com:.insteadOf", "https://github.com/")?;
+        std::env::set_current_dir(&repo_dir)?;
+
+        let (output, changed) =
+            test_helpers::capture_stdout(|| pull(&PullOptions::default(), &TextSink))?;
+
+        // git connects over SSH, but the remote stays spelled as configured
+        assert!(changed);
+        assert!(
+            output.contains("from 'https://github.com/owner/old-name.git'"),
+            "{}",
+            output
+        );
+        let renamed = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(
+            renamed.config()?.get_string("remote.origin.url")?,
+            "https://github.com/owner/new-name.git"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_rewrites_remote_with_clone_url_template() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        repo.remote("origin", "https://mirror.acme.com/owner/old-name.git")?;
+
+        let config = Config::load_from(temp.path().join(".config/git-repo-name"))?;
+        config.set_repo_path(Some(repo_dir));
+        config.set_clone_url_template("https://mirror.acme.com/{owner}/{repo}.git")?;
+        let (_, changed) = test_helpers::capture_stdout(|| {
+            pull_with(
+                &config,
+                &PullOptions {
+                    no_rename_dir: true,
+                    ..Default::default()
+                },
+                &TextSink,
+            )
+        })?;
+
+        assert!(changed);
+        assert_eq!(
+            repo.find_remote("origin")?.url(),
+            Some("https://mirror.acme.com/owner/new-name.git")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_remote_url_override_leaves_remote_alone() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::create_bare_repo(&temp, "other.git")?;
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        let origin_url = "https://github.com/owner/old-name.git";
+        repo.remote("origin", origin_url)?;
+        std::env::set_current_dir(&repo_dir)?;
+
+        let (_, changed) = test_helpers::capture_stdout(|| {
+            pull(
+                &PullOptions {
+                    remote_url: Some("file://../other.git".into()),
+                    ..Default::default()
+                },
+                &TextSink,
+            )
+        })?;
+
+        assert!(changed);
+        let renamed = Repository::open(temp.path().join("other"))?;
+        assert_eq!(git::get_remote_url(&renamed)?, origin_url);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_remote_url_override_leaves_remote_alone() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::create_bare_repo(&temp, "other.git")?;
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "new-name")?;
+        let origin_url = "https://github.com/owner/new-name.git";
+        repo.remote("origin", origin_url)?;
+        std::env::set_current_dir(&repo_dir)?;
+
+        test_helpers::capture_stdout(|| {
+            push(&PushOptions {
+                remote_url: Some("../other.git".into()),
+                ..Default::default()
+            })
+        })?;
+
+        test_helpers::assert_directory_existence(&temp, "new-name.git", true)?;
+        assert_eq!(git::get_remote_url(&repo)?, origin_url);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_create_remote() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let upstream = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let (repo_dir, _) = test_helpers::create_main_repo(&temp, "old-name")?;
+        std::env::set_current_dir(&repo_dir)?;
+
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            pull(
+                &PullOptions {
+                    remote_url: Some(upstream.display().to_string()),
+                    create_remote: true,
+                    ..Default::default()
+                },
+                &TextSink,
+            )
+        })?;
+
+        assert!(changed);
+        assert!(output.contains("Adding 'origin' remote"), "{}", output);
+        test_helpers::assert_directory_existence(&temp, "new-name", true)?;
+        let renamed = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(
+            git::get_remote_url(&renamed)?,
+            upstream.canonicalize()?.display().to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_create_remote_next_to_another_remote() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let upstream = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        repo.remote("upstream", "https://github.com/owner/fork.git")?;
+
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_repo_path(Some(repo_dir));
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            pull_with(
+                &config,
+                &PullOptions {
+                    remote_url: Some(upstream.display().to_string()),
+                    create_remote: true,
+                    ..Default::default()
+                },
+                &TextSink,
+            )
+        })?;
+
+        // The only remote isn't mistaken for the default one
+        assert!(changed);
+        assert!(output.contains("Adding 'origin' remote"), "{}", output);
+        let renamed = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(
+            git::get_remote_url(&renamed)?,
+            upstream.canonicalize()?.display().to_string()
+        );
+        assert_eq!(
+            renamed.find_remote("upstream")?.url(),
+            Some("https://github.com/owner/fork.git")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_with_explicit_config() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let upstream = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        repo.remote("upstream", &upstream.display().to_string())?;
+
+        // Nothing here is visible to the global CONFIG, which would use "origin"
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_repo_path(Some(repo_dir));
+        config.set_remote("upstream".to_string());
+
+        let (_, changed) = test_helpers::capture_stdout(|| {
+            pull_with(&config, &PullOptions::default(), &TextSink)
+        })?;
+
+        assert!(changed);
+        test_helpers::assert_directory_existence(&temp, "old-name", false)?;
+        test_helpers::assert_directory_existence(&temp, "new-name", true)?;
+        assert!(temp.path().join("config/config").exists());
+
+        Ok(())
+    }
 }
@@ -1,13 +1,36 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use git_repo_name::{
-    config::CONFIG,
-    fetch_repo_name, pull, push,
-    types::{Error, Result},
+    align, batch, canonical_remote_url, clone, completion,
+    config::{PullNameSource, CONFIG},
+    describe_fetch, doctor, fetch_all_remotes, fetch_by_directory, fetch_repo_name, login,
+    progress::{JsonSink, ProgressSink, TextSink},
+    pull, push,
+    types::{AlignOptions, CloneOptions, Error, FetchOptions, PullOptions, PushOptions, Result},
+    utils::color::{self, ColorChoice},
 };
 
+/// Exit code for `pull --dry-run` and `align --dry-run` when the directory name or remote URL is out of sync.
+const DRIFT_EXIT_CODE: i32 = 2;
+
 #[derive(Parser)]
 #[command(author, version, about, bin_name = "git-repo-name")]
 struct Cli {
+    /// Print diagnostic output to stderr (-v for debug, -vv for trace)
+    #[arg(short = 'v', long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Operate on the repository at this path instead of the current directory
+    #[arg(short = 'C', long = "path", global = true, value_name = "PATH")]
+    path: Option<std::path::PathBuf>,
+
+    /// Use the settings of this config profile (defaults to $GIT_REPO_NAME_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// When to color output: auto (if stdout is a terminal and NO_COLOR is unset), always or never
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -17,22 +40,188 @@ enum Commands {
     Fetch {
         #[arg(short = 'r', long)]
         remote: Option<String>,
+
+        /// Use this URL instead of the remote's URL (takes precedence over --remote)
+        #[arg(long)]
+        remote_url: Option<String>,
+
+        #[arg(long)]
+        emit_env: bool,
+
+        /// Print only the name, without the URL or a trailing newline
+        #[arg(short = '0', long, conflicts_with = "emit_env")]
+        no_newline: bool,
+
+        /// Print what would be looked up without contacting GitHub or the file system
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Check whether a repository named after the local directory exists under your GitHub account
+        #[arg(long, conflicts_with_all = ["remote", "remote_url", "emit_env", "no_newline", "dry_run"])]
+        by_directory: bool,
+
+        /// Resolve every configured remote and print its repository name and URL
+        #[arg(long, conflicts_with_all = ["remote", "remote_url", "emit_env", "no_newline", "dry_run", "by_directory"])]
+        all_remotes: bool,
+
+        /// Print the repository through a template, e.g. '{owner}/{name}'. Placeholders: {name}, {clone_url}, plus {owner} and {full_name} for GitHub or {path} for file remotes
+        #[arg(long, conflicts_with_all = ["emit_env", "no_newline", "dry_run", "by_directory", "all_remotes"])]
+        format: Option<String>,
     },
 
     Pull {
         #[arg(short = 'r', long)]
         remote: Option<String>,
 
+        /// Use this URL instead of the remote's URL (takes precedence over --remote)
+        #[arg(long)]
+        remote_url: Option<String>,
+
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Take the repository name from the GitHub API ("api") or the remote URL ("url")
+        #[arg(long)]
+        name_source: Option<PullNameSource>,
+
+        /// Print the commands that would sync the repository instead of running them
+        #[arg(long)]
+        print_commands: bool,
+
+        /// Rename the directory even if the working tree has uncommitted changes
+        #[arg(short = 'f', long)]
+        force: bool,
+
+        /// For file remotes, take the name from the URL without requiring the path to exist
+        #[arg(long, visible_alias = "no-verify")]
+        offline: bool,
+
+        /// Only rename the directory, keeping the remote URL as is
+        #[arg(long, conflicts_with = "no_rename_dir")]
+        no_rename_remote: bool,
+
+        /// Only update the remote URL, keeping the directory name as is
+        #[arg(long)]
+        no_rename_dir: bool,
+
+        /// Keep a relative file remote URL as written instead of making it absolute
+        #[arg(long)]
+        keep_relative: bool,
+
+        /// Add the remote with --remote-url if the repository doesn't have it yet
+        #[arg(long, requires = "remote_url")]
+        create_remote: bool,
+
+        /// If the repository is a submodule, update its path in the parent repository too
+        #[arg(long)]
+        update_submodule_ref: bool,
+
+        /// Pull every git repository directly below the current directory (or -C path)
+        #[arg(short = 'R', long, conflicts_with = "remote_url")]
+        recursive: bool,
+
+        /// With --recursive, how many repositories to process at once
+        #[arg(short = 'j', long, requires = "recursive", default_value_t = batch::DEFAULT_JOBS, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+
+        /// With --recursive, how many directory levels below the start path to look
+        /// for repositories. Repositories' own subdirectories are never searched
+        #[arg(long, requires = "recursive", default_value_t = batch::DEFAULT_DEPTH, value_parser = clap::value_parser!(u16).range(1..))]
+        depth: u16,
+
+        /// With --recursive, skip repositories found in sync by a run in the last 24
+        /// hours whose directory name and remote URL haven't changed (--force checks
+        /// them anyway)
+        #[arg(long, requires = "recursive")]
+        cache: bool,
+
+        /// Don't print anything for repositories that are already up-to-date
+        #[arg(short = 'q', long)]
+        quiet: bool,
+
+        /// Report progress as one JSON object per line instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rename the directory and normalize the remote URL in one step
+    Align {
+        #[arg(short = 'r', long)]
+        remote: Option<String>,
+
+        /// Use this URL instead of the remote's URL (takes precedence over --remote)
+        #[arg(long)]
+        remote_url: Option<String>,
+
         #[arg(short = 'n', long)]
         dry_run: bool,
+
+        /// Apply the changes without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Rename the directory even if the working tree has uncommitted changes
+        #[arg(short = 'f', long)]
+        force: bool,
     },
 
     Push {
         #[arg(short = 'r', long)]
         remote: Option<String>,
 
+        /// Use this URL instead of the remote's URL (takes precedence over --remote)
+        #[arg(long)]
+        remote_url: Option<String>,
+
         #[arg(short = 'n', long)]
         dry_run: bool,
+
+        #[arg(long)]
+        description: Option<String>,
+
+        #[arg(long = "topic")]
+        topics: Vec<String>,
+
+        /// Make this existing branch the repository's default branch
+        #[arg(long)]
+        default_branch: Option<String>,
+
+        #[arg(long)]
+        safety_check: bool,
+
+        /// Transfer the repository to this user or organization
+        #[arg(long)]
+        owner: Option<String>,
+
+        /// Rename the remote repository to this instead of the local directory name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Skip checking that the GitHub token is valid before changing anything
+        #[arg(long)]
+        no_verify_token: bool,
+    },
+
+    /// Clone a repository into a directory named after its canonical name
+    Clone {
+        url: String,
+
+        /// Print what would be cloned where without cloning
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Authorize with GitHub in the browser and store the token
+    Login,
+
+    /// Print the remote URL `pull` would set, without changing anything
+    ResolveUrl {
+        #[arg(short = 'r', long)]
+        remote: Option<String>,
+
+        /// Resolve this URL instead of the remote's URL (takes precedence over --remote)
+        #[arg(long)]
+        remote_url: Option<String>,
     },
 
     Config {
@@ -40,67 +229,444 @@ enum Commands {
 
         value: Option<String>,
     },
+
+    /// Check configuration, GitHub connectivity and the current repository
+    Doctor,
+
+    /// Prints completion candidates for the given words, used by shell integration.
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        words: Vec<String>,
+    },
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+/// Whether git ran us for `git repo-name`. git execs `git-repo-name` for unknown
+/// subcommands and sets `GIT_EXEC_PATH` when it does.
+fn invoked_by_git() -> bool {
+    let is_git_repo_name = std::env::args_os()
+        .next()
+        .and_then(|arg0| {
+            std::path::Path::new(&arg0)
+                .file_stem()
+                .map(|stem| stem == "git-repo-name")
+        })
+        .unwrap_or(false);
+    is_git_repo_name && std::env::var_os("GIT_EXEC_PATH").is_some()
+}
+
+/// Runs the CLI and returns the process exit code on success.
+fn run() -> Result<i32> {
+    let mut command = Cli::command();
+    if invoked_by_git() {
+        // Show usage the way the user typed it
+        command = command.bin_name("git repo-name");
+    }
+    let cli = Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit());
+    init_logger(cli.verbose);
+    color::set_choice(cli.color);
+    if cli.path.is_some() {
+        CONFIG.set_repo_path(cli.path);
+    }
+    let profile = cli.profile.or_else(|| {
+        std::env::var("GIT_REPO_NAME_PROFILE")
+            .ok()
+            .filter(|s| !s.is_empty())
+    });
+    if profile.is_some() {
+        // Setting a value is how a new profile gets created
+        let create = matches!(cli.command, Commands::Config { value: Some(_), .. });
+        CONFIG.set_profile(profile, create)?;
+    }
 
     match cli.command {
-        Commands::Fetch { remote } => {
+        Commands::Fetch {
+            remote,
+            remote_url,
+            emit_env,
+            no_newline,
+            dry_run,
+            by_directory,
+            all_remotes,
+            format,
+        } => {
             if let Some(remote_name) = remote {
                 CONFIG.set_remote(remote_name);
             }
-            fetch_repo_name()?;
-            Ok(())
+            let options = FetchOptions {
+                emit_env,
+                remote_url,
+                dry_run,
+                no_newline,
+                by_directory,
+                all_remotes,
+                format,
+            };
+            if by_directory {
+                fetch_by_directory()?;
+            } else if all_remotes {
+                let results = fetch_all_remotes()?;
+                if results.iter().any(|(_, result)| result.is_err()) {
+                    return Ok(1);
+                }
+            } else if dry_run {
+                println!("{}", describe_fetch(&options)?);
+            } else {
+                fetch_repo_name(&options)?;
+            }
+            Ok(0)
         }
-        Commands::Pull { remote, dry_run } => {
+        Commands::Pull {
+            remote,
+            remote_url,
+            dry_run,
+            name_source,
+            print_commands,
+            force,
+            offline,
+            no_rename_remote,
+            no_rename_dir,
+            keep_relative,
+            create_remote,
+            update_submodule_ref,
+            recursive,
+            jobs,
+            depth,
+            cache,
+            quiet,
+            json,
+        } => {
+            let options = PullOptions {
+                dry_run,
+                remote,
+                name_source,
+                print_commands,
+                force,
+                remote_url,
+                offline,
+                no_rename_remote,
+                no_rename_dir,
+                keep_relative,
+                update_submodule_ref,
+                quiet,
+                create_remote,
+            };
+            let sink: &dyn ProgressSink = if json { &JsonSink } else { &TextSink };
+            if recursive {
+                let root = match CONFIG.get_repo_path() {
+                    Some(path) => path,
+                    None => std::env::current_dir()?,
+                };
+                let cache_path = CONFIG.get_pull_cache_path();
+                let mut pull_cache = cache.then(|| batch::PullCache::load(&cache_path));
+                let summary = batch::pull_recursive(
+                    &root,
+                    &options,
+                    jobs.into(),
+                    depth.into(),
+                    pull_cache.as_mut(),
+                    sink,
+                )?;
+                if let Some(pull_cache) = pull_cache {
+                    pull_cache.save(&cache_path)?;
+                }
+                if !json {
+                    println!("{}", summary.describe(dry_run));
+                }
+                return Ok(if summary.failed > 0 {
+                    1
+                } else if dry_run && summary.changed > 0 {
+                    DRIFT_EXIT_CODE
+                } else {
+                    0
+                });
+            }
+            let changed = pull(&options, sink)?;
+            Ok(if dry_run && changed {
+                DRIFT_EXIT_CODE
+            } else {
+                0
+            })
+        }
+        Commands::Align {
+            remote,
+            remote_url,
+            dry_run,
+            yes,
+            force,
+        } => {
             if let Some(remote_name) = remote {
                 CONFIG.set_remote(remote_name);
             }
-            pull(dry_run)
+            let changed = align(&AlignOptions {
+                dry_run,
+                yes,
+                force,
+                remote_url,
+            })?;
+            Ok(if dry_run && changed {
+                DRIFT_EXIT_CODE
+            } else {
+                0
+            })
         }
-        Commands::Push { remote, dry_run } => {
+        Commands::Push {
+            remote,
+            remote_url,
+            dry_run,
+            description,
+            topics,
+            default_branch,
+            safety_check,
+            owner,
+            name,
+            no_verify_token,
+        } => {
             if let Some(remote_name) = remote {
                 CONFIG.set_remote(remote_name);
             }
-            push(dry_run)
+            push(&PushOptions {
+                dry_run,
+                description,
+                topics,
+                default_branch,
+                safety_check,
+                owner,
+                remote_url,
+                verify_token: !no_verify_token,
+                name,
+            })?;
+            Ok(0)
+        }
+        Commands::Clone { url, dry_run } => {
+            clone(&CloneOptions { url, dry_run })?;
+            Ok(0)
+        }
+        Commands::Login => {
+            login()?;
+            Ok(0)
         }
-        Commands::Config { key, value } => match key.as_str() {
-            "github-token" => match value {
-                Some(token) => {
-                    CONFIG.set_github_token(&token)?;
-                    println!("GitHub token configured successfully");
-                    Ok(())
+        Commands::ResolveUrl { remote, remote_url } => {
+            if let Some(remote_name) = remote {
+                CONFIG.set_remote(remote_name);
+            }
+            println!("{}", canonical_remote_url(remote_url.as_deref())?);
+            Ok(0)
+        }
+        Commands::Config { key, value } => {
+            run_config(key, value)?;
+            Ok(0)
+        }
+        Commands::Doctor => Ok(if doctor::doctor()? { 0 } else { 1 }),
+        Commands::Complete { words } => {
+            for candidate in completion::complete(&words)? {
+                println!("{}", candidate);
+            }
+            Ok(0)
+        }
+    }
+}
+
+/// Logs warnings by default; each `-v` raises the level one step.
+fn init_logger(verbose: u8) {
+    let level = match verbose {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+
+    env_logger::Builder::new()
+        .filter_module("git_repo_name", level)
+        .filter_level(log::LevelFilter::Warn)
+        .format_timestamp(None)
+        .init();
+}
+
+fn run_config(key: String, value: Option<String>) -> Result<()> {
+    match key.as_str() {
+        "github-token" => match value {
+            Some(token) => {
+                CONFIG.set_github_token(&token)?;
+                println!("GitHub token configured successfully");
+                Ok(())
+            }
+            None => {
+                let token = CONFIG.get_github_token()?;
+                println!("{}", token);
+                Ok(())
+            }
+        },
+        "github-token-file" => match value {
+            Some(path) => {
+                CONFIG.set_github_token_file(&path)?;
+                println!("GitHub token file set to {}", path);
+                Ok(())
+            }
+            None => {
+                if let Some(path) = CONFIG.get_github_token_file() {
+                    println!("{}", path.display());
+                }
+                Ok(())
+            }
+        },
+        "api-base-url" => match value {
+            Some(url) => {
+                CONFIG.set_api_base_url(&url)?;
+                println!("GitHub API base URL set to {}", url);
+                Ok(())
+            }
+            None => {
+                if let Some(url) = CONFIG.get_api_base_url() {
+                    println!("{}", url);
+                }
+                Ok(())
+            }
+        },
+        "oauth-client-id" => match value {
+            Some(client_id) => {
+                CONFIG.set_oauth_client_id(&client_id)?;
+                println!("OAuth client ID set to {}", client_id);
+                Ok(())
+            }
+            None => {
+                if let Some(client_id) = CONFIG.get_oauth_client_id() {
+                    println!("{}", client_id);
                 }
-                None => {
-                    let token = CONFIG.get_github_token()?;
-                    println!("{}", token);
-                    Ok(())
+                Ok(())
+            }
+        },
+        "clone-url-template" => match value {
+            Some(template) => {
+                CONFIG.set_clone_url_template(&template)?;
+                println!("Clone URL template set to {}", template);
+                Ok(())
+            }
+            None => {
+                if let Some(template) = CONFIG.get_clone_url_template() {
+                    println!("{}", template);
                 }
-            },
-            "default-remote" => match value {
-                Some(remote) => {
-                    CONFIG.set_default_remote(&remote)?;
-                    println!("Default remote set to {}", remote);
-                    Ok(())
+                Ok(())
+            }
+        },
+        "github-api" => match value {
+            Some(backend) => {
+                CONFIG.set_github_api(backend.parse()?)?;
+                println!("GitHub API set to {}", backend);
+                Ok(())
+            }
+            None => {
+                println!("{}", CONFIG.get_github_api());
+                Ok(())
+            }
+        },
+        "github-shorthand" => match value {
+            Some(enabled) => {
+                CONFIG.set_github_shorthand(&enabled)?;
+                println!("GitHub shorthand set to {}", enabled);
+                Ok(())
+            }
+            None => {
+                println!("{}", CONFIG.get_github_shorthand());
+                Ok(())
+            }
+        },
+        "post-rename-hook" => match value {
+            Some(command) => {
+                CONFIG.set_post_rename_hook(&command)?;
+                println!("Post-rename hook set to {}", command);
+                Ok(())
+            }
+            None => {
+                if let Some(command) = CONFIG.get_post_rename_hook() {
+                    println!("{}", command);
                 }
-                None => {
-                    let remote = CONFIG.get_default_remote()?;
-                    println!("{}", remote);
-                    Ok(())
+                Ok(())
+            }
+        },
+        "audit-log" => match value {
+            Some(path) => {
+                CONFIG.set_audit_log(&path)?;
+                println!("Audit log set to {}", path);
+                Ok(())
+            }
+            None => {
+                if let Some(path) = CONFIG.get_audit_log() {
+                    println!("{}", path.display());
                 }
-            },
-            _ => Err(Error::Config(format!(
-                "Unknown config key: {}. Valid keys: github-token, default-remote",
-                key
-            ))),
+                Ok(())
+            }
         },
+        "default-remote" => match value {
+            Some(remote) => {
+                CONFIG.set_default_remote(&remote)?;
+                println!("Default remote set to {}", remote);
+                Ok(())
+            }
+            None => {
+                let remote = CONFIG.get_default_remote()?;
+                println!("{}", remote);
+                Ok(())
+            }
+        },
+        "use-upstream" => match value {
+            Some(enabled) => {
+                CONFIG.set_use_upstream(&enabled)?;
+                println!("Use upstream set to {}", enabled);
+                Ok(())
+            }
+            None => {
+                println!("{}", CONFIG.get_use_upstream());
+                Ok(())
+            }
+        },
+        "pull-name-source" => match value {
+            Some(source) => {
+                CONFIG.set_pull_name_source(source.parse()?)?;
+                println!("Pull name source set to {}", source);
+                Ok(())
+            }
+            None => {
+                let source = CONFIG.get_pull_name_source()?;
+                println!("{}", source);
+                Ok(())
+            }
+        },
+        "user-agent" => match value {
+            Some(user_agent) => {
+                CONFIG.set_user_agent(&user_agent)?;
+                println!("User agent set to {}", user_agent);
+                Ok(())
+            }
+            None => {
+                println!("{}", CONFIG.get_user_agent());
+                Ok(())
+            }
+        },
+        "http-timeout" => match value {
+            Some(seconds) => {
+                CONFIG.set_http_timeout(&seconds)?;
+                println!("HTTP timeout set to {} seconds", seconds);
+                Ok(())
+            }
+            None => {
+                println!("{}", CONFIG.get_http_timeout()?.as_secs_f64());
+                Ok(())
+            }
+        },
+        _ => Err(Error::Config(format!(
+            "Unknown config key: {}. Valid keys: github-token, github-token-file, default-remote, use-upstream, pull-name-source, user-agent, http-timeout, api-base-url, github-shorthand, github-api, clone-url-template, oauth-client-id, post-rename-hook, audit-log",
+            key
+        ))),
     }
 }
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    match run() {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(e.exit_code());
+        }
     }
 }
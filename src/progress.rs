@@ -0,0 +1,300 @@
+use crate::{
+    git,
+    types::Result,
+    utils::{color, fs},
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A change `pull` made (or, in dry-run mode, would make) to a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SyncOutcome {
+    /// The remote reported that the repository was renamed or transferred.
+    Moved { from: String, to: String },
+    /// Neither the directory name nor the remote URL needed changing.
+    UpToDate,
+    /// Only one of the two needs changing; `part` is the one already in sync.
+    PartiallyInSync { part: SyncPart, dry_run: bool },
+    RemoteUrl {
+        remote: String,
+        from: String,
+        to: String,
+        dry_run: bool,
+    },
+    /// The remote was missing and `--create-remote` added it.
+    RemoteAdded {
+        remote: String,
+        url: String,
+        dry_run: bool,
+    },
+    Directory {
+        from: PathBuf,
+        to: PathBuf,
+        /// Linked worktrees whose pointers follow the renamed directory.
+        worktrees: Vec<PathBuf>,
+        dry_run: bool,
+    },
+    /// The superproject's reference to the renamed directory, a submodule.
+    SubmoduleRef {
+        superproject: PathBuf,
+        from: String,
+        to: String,
+        dry_run: bool,
+    },
+    /// The configured `post-rename-hook`, run after the directory was renamed.
+    Hook { command: String, dry_run: bool },
+    /// A shell command that would apply a change, with `--print-commands`.
+    Command { command: String },
+}
+
+/// The two things `pull` keeps in sync with the remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPart {
+    Directory,
+    RemoteUrl,
+}
+
+/// Receives progress events from `pull`, so callers decide how they are shown.
+/// Changes are reported once they have been applied, or in dry-run mode instead
+/// of applying them.
+pub trait ProgressSink {
+    /// Called by batch operations before a repository is processed.
+    fn on_repo_start(&self, path: &Path);
+    fn on_change(&self, outcome: &SyncOutcome);
+    /// Called by batch operations after a repository was processed, with whether
+    /// anything changed.
+    fn on_repo_done(&self, path: &Path, result: &Result<bool>);
+}
+
+/// Prints human-readable progress to stdout, the default for the CLI.
+pub struct TextSink;
+
+impl ProgressSink for TextSink {
+    fn on_repo_start(&self, path: &Path) {
+        println!("{}:", path.display());
+    }
+
+    fn on_change(&self, outcome: &SyncOutcome) {
+        match outcome {
+            SyncOutcome::Moved { from, to } => {
+                println!("Repository '{}' has moved to '{}'", from, to);
+            }
+            SyncOutcome::UpToDate => {
+                println!("Directory name and remote URL already up-to-date");
+            }
+            SyncOutcome::PartiallyInSync { part, dry_run } => {
+                let verb = if *dry_run { "would" } else { "will" };
+                match part {
+                    SyncPart::Directory => println!(
+                        "Directory name already correct; {} update remote URL only",
+                        verb
+                    ),
+                    SyncPart::RemoteUrl => {
+                        println!("Remote URL already correct; {} rename directory only", verb)
+                    }
+                }
+            }
+            SyncOutcome::RemoteUrl {
+                remote,
+                from,
+                to,
+                dry_run,
+            } => {
+                println!(
+                    "{} '{}' remote from '{}' to '{}'",
+                    if *dry_run { "Would change" } else { "Changing" },
+                    remote,
+                    color::old(&git::redact_credentials(from)),
+                    color::new(&git::redact_credentials(to))
+                );
+            }
+            SyncOutcome::RemoteAdded {
+                remote,
+                url,
+                dry_run,
+            } => {
+                println!(
+                    "{} '{}' remote '{}'",
+                    if *dry_run { "Would add" } else { "Adding" },
+                    remote,
+                    color::new(&git::redact_credentials(url))
+                );
+            }
+            SyncOutcome::Directory {
+                from,
+                to,
+                worktrees,
+                dry_run,
+            } => {
+                if !worktrees.is_empty() {
+                    println!(
+                        "{} {} linked worktree(s) to follow the renamed directory:",
+                        if *dry_run { "Would update" } else { "Updating" },
+                        worktrees.len()
+                    );
+                    for worktree in worktrees {
+                        println!("  {}", worktree.display());
+                    }
+                }
+
+                let (from, to) = (fs::display_path(from), fs::display_path(to));
+                let (old, new) = (color::old(&from), color::new(&to));
+                if *dry_run {
+                    println!("Would rename directory from '{}' to '{}'", old, new);
+                } else {
+                    println!("Renaming directory from '{}' to '{}'...", old, new);
+                    // Output a machine-readable marker for the shell wrapper to detect
+                    println!("GRN_DIR_CHANGE:{}:{}", from, to);
+                }
+            }
+            SyncOutcome::SubmoduleRef {
+                superproject,
+                from,
+                to,
+                dry_run,
+            } => {
+                println!(
+                    "{} submodule path in '{}' from '{}' to '{}'",
+                    if *dry_run { "Would update" } else { "Updating" },
+                    fs::display_path(superproject),
+                    from,
+                    to
+                );
+            }
+            SyncOutcome::Hook { command, dry_run } => println!(
+                "{} post-rename hook: {}",
+                if *dry_run { "Would run" } else { "Running" },
+                command
+            ),
+            SyncOutcome::Command { command } => println!("{}", command),
+        }
+    }
+
+    fn on_repo_done(&self, _path: &Path, result: &Result<bool>) {
+        if let Err(e) = result {
+            println!("{}", e);
+        }
+    }
+}
+
+/// Prints one JSON object per event (NDJSON) to stdout, for tools wrapping the CLI.
+pub struct JsonSink;
+
+impl JsonSink {
+    fn emit(&self, event: serde_json::Value) {
+        println!("{}", event);
+    }
+}
+
+impl ProgressSink for JsonSink {
+    fn on_repo_start(&self, path: &Path) {
+        self.emit(serde_json::json!({ "event": "repo_start", "path": path }));
+    }
+
+    fn on_change(&self, outcome: &SyncOutcome) {
+        let mut event = serde_json::to_value(outcome).unwrap_or_default();
+        event["event"] = "change".into();
+        match outcome {
+            SyncOutcome::RemoteUrl { from, to, .. } => {
+                event["from"] = git::redact_credentials(from).into();
+                event["to"] = git::redact_credentials(to).into();
+            }
+            SyncOutcome::RemoteAdded { url, .. } => {
+                event["url"] = git::redact_credentials(url).into();
+            }
+            _ => {}
+        }
+        self.emit(event);
+    }
+
+    fn on_repo_done(&self, path: &Path, result: &Result<bool>) {
+        self.emit(match result {
+            Ok(changed) => {
+                serde_json::json!({ "event": "repo_done", "path": path, "changed": changed })
+            }
+            Err(e) => {
+                serde_json::json!({ "event": "repo_done", "path": path, "error": e.to_string() })
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{batch, test_helpers, types::PullOptions};
+    use std::cell::RefCell;
+
+    /// Records every event as a short string.
+    #[derive(Default)]
+    struct RecordingSink {
+        events: RefCell<Vec<String>>,
+    }
+
+    impl ProgressSink for RecordingSink {
+        fn on_repo_start(&self, path: &Path) {
+            self.events.borrow_mut().push(format!(
+                "start {}",
+                path.file_name().unwrap().to_string_lossy()
+            ));
+        }
+
+        fn on_change(&self, outcome: &SyncOutcome) {
+            self.events.borrow_mut().push(match outcome {
+                SyncOutcome::Directory { from, to, .. } => format!(
+                    "rename {} -> {}",
+                    from.file_name().unwrap().to_string_lossy(),
+                    to.file_name().unwrap().to_string_lossy()
+                ),
+                other => format!("{:?}", other),
+            });
+        }
+
+        fn on_repo_done(&self, path: &Path, result: &Result<bool>) {
+            self.events.borrow_mut().push(format!(
+                "done {} {:?}",
+                path.file_name().unwrap().to_string_lossy(),
+                result.as_ref().ok()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_events_for_rename() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let bare = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        std::fs::create_dir(temp.path().join("src"))?;
+        let (_, repo) = test_helpers::create_main_repo(&temp, "src/old-name")?;
+        repo.remote("origin", &bare.display().to_string())?;
+
+        let sink = RecordingSink::default();
+        let (output, _) = test_helpers::capture_stdout(|| {
+            batch::pull_recursive(
+                &temp.path().join("src"),
+                &PullOptions::default(),
+                1,
+                1,
+                None,
+                &sink,
+            )
+        })?;
+
+        assert_eq!(
+            *sink.events.borrow(),
+            vec![
+                "start old-name".to_string(),
+                "PartiallyInSync { part: RemoteUrl, dry_run: false }".to_string(),
+                "rename old-name -> new-name".to_string(),
+                "done old-name Some(true)".to_string(),
+            ]
+        );
+        assert!(output.is_empty(), "Unexpected output: {}", output);
+
+        Ok(())
+    }
+}
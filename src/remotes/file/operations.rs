@@ -1,46 +1,49 @@
 use crate::{
     git,
-    remotes::file,
-    types::{Error, Result},
+    progress::TextSink,
+    remotes::{
+        self,
+        file::{self, provider::FileProvider},
+    },
+    types::{Error, PullOptions, Result},
     utils::fs,
 };
 use git2::Repository;
+use log::debug;
 use std::path::Path;
 
-pub fn pull_from_file_remote(repo: &Repository, remote_url: &str, dry_run: bool) -> Result<()> {
-    let local_directory_name = git::get_local_directory_name(repo)?;
-    let canonical_path = fs::resolve_canonical_path(Path::new(&remote_url))?;
-    let resolved_repo_name = git::extract_repo_name_from_path(&canonical_path)?;
-
-    let repo_path = repo
-        .workdir()
-        .ok_or_else(|| Error::Fs("Cannot get repository working directory".into()))?;
-
-    let resolved_remote_url = file::url::format_new_remote_url(remote_url, &canonical_path)?;
-    let should_rename_directory = local_directory_name != resolved_repo_name;
-    let should_change_remote = resolved_remote_url != remote_url;
-
-    if !should_rename_directory && !should_change_remote {
-        println!("Directory name and remote URL already up-to-date");
-        return Ok(());
-    }
-
-    if should_change_remote {
-        git::set_remote_url(repo, remote_url, &resolved_remote_url, dry_run)?;
-    }
-
-    if should_rename_directory {
-        fs::rename_directory(repo_path, &resolved_repo_name, dry_run)?;
-    }
-
-    Ok(())
+/// Returns whether the directory name or remote URL was (or, in dry-run mode,
+/// would be) changed.
+pub fn pull_from_file_remote(repo: &Repository, remote_url: &str, dry_run: bool) -> Result<bool> {
+    let options = PullOptions {
+        dry_run,
+        ..Default::default()
+    };
+    remotes::pull(
+        &FileProvider { offline: false },
+        repo,
+        remote_url,
+        &options,
+        &TextSink,
+    )
 }
 
+/// Renames the remote repository directory after the local working directory and
+/// points the remote at it. The local directory name is the source of truth, so
+/// the working copy itself never needs renaming: afterwards both sides match and
+/// a `pull` finds nothing to do.
 pub fn push_to_file_remote(repo: &Repository, remote_url: &str, dry_run: bool) -> Result<()> {
     let local_directory_name = git::get_local_directory_name(repo)?;
 
-    let remote_path = remote_url.trim_start_matches("file://");
-    if !Path::new(remote_path).exists() {
+    if file::url::ssh_remote_path(remote_url).is_some() {
+        return Err(Error::Fs(format!(
+            "Cannot rename a remote repository over SSH: {}",
+            remote_url
+        )));
+    }
+
+    let remote_path = fs::expand_tilde(Path::new(remote_url.trim_start_matches("file://")));
+    if !remote_path.exists() {
         return Err(Error::Fs(format!(
             "Remote repository does not exist: {}",
             remote_url
@@ -49,6 +52,12 @@ pub fn push_to_file_remote(repo: &Repository, remote_url: &str, dry_run: bool) -
 
     let canonical_path = fs::resolve_canonical_path(Path::new(remote_url))?;
     let remote_repo_name = git::extract_repo_name_from_path(&canonical_path)?;
+    debug!(
+        "Pushing '{}' to {}: should_rename_remote={}",
+        local_directory_name,
+        canonical_path,
+        remote_repo_name != local_directory_name
+    );
 
     if remote_repo_name == local_directory_name {
         println!("Remote repository name already matches the local directory name");
@@ -61,28 +70,33 @@ pub fn push_to_file_remote(repo: &Repository, remote_url: &str, dry_run: bool) -
             .unwrap_or(&canonical_path),
     );
 
+    // A remote pointing into a working clone's `.git` directory is named after the
+    // clone (as `pull` sees it), so rename the clone and keep pointing inside it
+    let (fs_path, inside_working_clone) = match fs_path.file_name() {
+        Some(name) if name == ".git" => (fs_path.parent().unwrap(), true),
+        _ => (fs_path, false),
+    };
+
+    // Only keep a `.git` suffix if the remote had one; non-bare remotes are often
+    // plain directories named after the repository
+    let remote_dir_name = fs_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::Fs("Cannot get remote repository directory name".into()))?;
+    let suffix = &remote_dir_name[git::strip_git_suffix(remote_dir_name).len()..];
+    let new_dir_name = format!("{}{}", local_directory_name, suffix);
+
     let parent_dir = fs_path.parent().unwrap();
-    let old_repo_path = parent_dir.join(format!("{}.git", remote_repo_name));
-    let new_repo_path = parent_dir.join(format!("{}.git", local_directory_name));
+    let mut new_repo_path = parent_dir.join(&new_dir_name);
+    if inside_working_clone {
+        new_repo_path.push(".git");
+    }
 
     let new_canonical_path = format!("file://{}", new_repo_path.display());
     let new_remote_url = file::url::format_new_remote_url(remote_url, &new_canonical_path)?;
 
-    fs::rename_directory(
-        &old_repo_path,
-        &format!("{}.git", local_directory_name),
-        dry_run,
-    )?;
-    if dry_run {
-        println!(
-            "Would change 'origin' remote from '{}' to '{}'",
-            remote_url, new_remote_url
-        );
-        return Ok(());
-    }
-    git::set_remote_url(repo, remote_url, &new_remote_url, dry_run)?;
-
-    Ok(())
+    fs::rename_directory(fs_path, &new_dir_name, dry_run)?;
+    remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, dry_run)
 }
 
 #[cfg(test)]
@@ -166,6 +180,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pull_quiet() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("test-repo.git", "test-repo")?;
+        let relative_remote_url = "file://../test-repo.git";
+        pull_test_setup.repo.remote("origin", relative_remote_url)?;
+        let options = PullOptions {
+            quiet: true,
+            ..Default::default()
+        };
+        let pull = || {
+            let remote_url = git::get_remote_url(&pull_test_setup.repo)?;
+            remotes::pull(
+                &FileProvider { offline: false },
+                &pull_test_setup.repo,
+                &remote_url,
+                &options,
+                &TextSink,
+            )
+        };
+
+        // A real change is still reported
+        let (output, changed) = test_helpers::capture_stdout(pull)?;
+        assert!(changed);
+        assert!(
+            output.contains("Changing 'origin' remote"),
+            "Expected remote URL update message, got: {}",
+            output
+        );
+
+        let (output, changed) = test_helpers::capture_stdout(pull)?;
+        assert!(!changed);
+        assert!(output.is_empty(), "Expected no output, got: {}", output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_keep_relative() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("new-name.git", "old-name")?;
+        let relative_remote_url = "file://../new-name.git";
+        pull_test_setup.repo.remote("origin", relative_remote_url)?;
+        let options = PullOptions {
+            keep_relative: true,
+            ..Default::default()
+        };
+
+        let (_, changed) = test_helpers::capture_stdout(|| {
+            remotes::pull(
+                &FileProvider { offline: false },
+                &pull_test_setup.repo,
+                relative_remote_url,
+                &options,
+                &TextSink,
+            )
+        })?;
+
+        // The directory follows the remote, but the URL isn't made absolute
+        assert!(changed);
+        test_helpers::assert_directory_existence(&pull_test_setup.temp, "new-name", true)?;
+        let renamed = Repository::open(pull_test_setup.temp.path().join("new-name"))?;
+        assert_eq!(relative_remote_url, git::get_remote_url(&renamed)?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_pull_remote_url_update_dry_run() -> anyhow::Result<()> {
         let pull_test_setup = setup_for_pull_test("test-repo.git", "test-repo")?;
@@ -173,7 +252,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", relative_remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_file_remote(&pull_test_setup.repo, &relative_remote_url, true)
+            pull_from_file_remote(&pull_test_setup.repo, relative_remote_url, true)
         })?;
 
         assert!(
@@ -199,7 +278,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", relative_remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_file_remote(&pull_test_setup.repo, &relative_remote_url, false)
+            pull_from_file_remote(&pull_test_setup.repo, relative_remote_url, false)
         })?;
 
         assert!(
@@ -210,6 +289,11 @@ mod tests {
             "Expected remote URL update message, got: {}",
             output
         );
+        assert!(
+            output.contains("Directory name already correct; will update remote URL only"),
+            "Expected partial sync note, got: {}",
+            output
+        );
 
         Ok(())
     }
@@ -267,6 +351,11 @@ mod tests {
             "Expected directory rename message, got: {}",
             output
         );
+        assert!(
+            output.contains("Remote URL already correct; will rename directory only"),
+            "Expected partial sync note, got: {}",
+            output
+        );
 
         test_helpers::assert_directory_existence(&pull_test_setup.temp, "old-name", false)?;
         test_helpers::assert_directory_existence(&pull_test_setup.temp, "new-name", true)?;
@@ -278,12 +367,10 @@ mod tests {
     fn test_pull_both_updates_dry_run() -> anyhow::Result<()> {
         let pull_test_setup = setup_for_pull_test("new-name.git", "old-name")?;
         let relative_remote_url = "file://../new-name.git";
-        pull_test_setup
-            .repo
-            .remote("origin", &relative_remote_url)?;
+        pull_test_setup.repo.remote("origin", relative_remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_file_remote(&pull_test_setup.repo, &relative_remote_url, true)
+            pull_from_file_remote(&pull_test_setup.repo, relative_remote_url, true)
         })?;
         let parent_dir = pull_test_setup
             .bare_repo_path
@@ -330,7 +417,7 @@ mod tests {
             .canonicalize()?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_file_remote(&pull_test_setup.repo, &relative_remote_url, false)
+            pull_from_file_remote(&pull_test_setup.repo, relative_remote_url, false)
         })?;
 
         assert!(
@@ -353,6 +440,230 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pull_no_rename_remote_or_dir() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("new-name.git", "old-name")?;
+        let relative_remote_url = "file://../new-name.git";
+        pull_test_setup.repo.remote("origin", relative_remote_url)?;
+        let temp = &pull_test_setup.temp;
+
+        // Only the remote URL changes
+        let options = PullOptions {
+            no_rename_dir: true,
+            ..Default::default()
+        };
+        test_helpers::capture_stdout(|| {
+            remotes::pull(
+                &FileProvider { offline: false },
+                &pull_test_setup.repo,
+                relative_remote_url,
+                &options,
+                &TextSink,
+            )
+        })?;
+        assert_eq!(
+            pull_test_setup.canonical_remote_url,
+            git::get_remote_url(&pull_test_setup.repo)?
+        );
+        test_helpers::assert_directory_existence(temp, "old-name", true)?;
+
+        // Only the directory changes
+        pull_test_setup
+            .repo
+            .remote_set_url("origin", relative_remote_url)?;
+        let options = PullOptions {
+            no_rename_remote: true,
+            ..Default::default()
+        };
+        test_helpers::capture_stdout(|| {
+            remotes::pull(
+                &FileProvider { offline: false },
+                &pull_test_setup.repo,
+                relative_remote_url,
+                &options,
+                &TextSink,
+            )
+        })?;
+        test_helpers::assert_directory_existence(temp, "old-name", false)?;
+        test_helpers::assert_directory_existence(temp, "new-name", true)?;
+        let renamed = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(relative_remote_url, git::get_remote_url(&renamed)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_update_submodule_ref() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let bare_repo_path = test_helpers::create_bare_repo(&temp, "new-name.git")?;
+        let (super_dir, superproject) = test_helpers::create_main_repo(&temp, "super")?;
+        let (_, repo) = test_helpers::create_main_repo(&temp, "super/old-name")?;
+        let remote_url = bare_repo_path.display().to_string();
+        repo.remote("origin", &remote_url)?;
+
+        // Register the repository as a submodule of the superproject
+        let signature = git2::Signature::now("Test", "test@example.com")?;
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+        std::fs::write(
+            super_dir.join(".gitmodules"),
+            format!(
+                "[submodule \"old-name\"]\n\tpath = old-name\n\turl = {}\n",
+                remote_url
+            ),
+        )?;
+        let mut index = superproject.index()?;
+        index.add_path(Path::new("old-name"))?;
+        index.write()?;
+
+        let options = PullOptions {
+            update_submodule_ref: true,
+            ..Default::default()
+        };
+        let (output, _) = test_helpers::capture_stdout(|| {
+            remotes::pull(
+                &FileProvider { offline: false },
+                &repo,
+                &remote_url,
+                &options,
+                &TextSink,
+            )
+        })?;
+
+        assert!(
+            output.contains("Updating submodule path in"),
+            "Expected submodule message, got: {}",
+            output
+        );
+        test_helpers::assert_directory_existence(&temp, "super/new-name", true)?;
+        let gitmodules = git2::Config::open(&super_dir.join(".gitmodules"))?;
+        assert_eq!(
+            gitmodules.get_string("submodule.old-name.path")?,
+            "new-name"
+        );
+        let mut index = superproject.index()?;
+        index.read(true)?;
+        assert!(index.get_path(Path::new("old-name"), 0).is_none());
+        let entry = index
+            .get_path(Path::new("new-name"), 0)
+            .expect("Submodule should be staged at its new path");
+        assert_eq!(entry.mode, 0o160000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_offline_nonexistent_remote() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let (_repo_dir, repo) = test_helpers::create_main_repo(&temp, "test-repo")?;
+        let remote_url = "/mnt/nas/unmounted/new-name.git";
+        repo.remote("origin", remote_url)?;
+        let options = PullOptions {
+            dry_run: true,
+            offline: true,
+            ..Default::default()
+        };
+
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            remotes::pull(
+                &FileProvider { offline: true },
+                &repo,
+                remote_url,
+                &options,
+                &TextSink,
+            )
+        })?;
+        assert!(changed);
+        assert!(
+            output.contains("Would rename directory") && output.contains("new-name"),
+            "Expected directory rename message, got: {}",
+            output
+        );
+        assert!(
+            !output.contains("remote from"),
+            "Remote URL shouldn't change: {}",
+            output
+        );
+
+        let online = remotes::pull(
+            &FileProvider { offline: false },
+            &repo,
+            remote_url,
+            &options,
+            &TextSink,
+        );
+        assert!(matches!(online, Err(Error::Fs(_))), "Expected a path error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_non_bare_remote() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        std::fs::create_dir(temp.path().join("remotes"))?;
+        let (remote_dir, _) = test_helpers::create_main_repo(&temp, "remotes/new-name")?;
+        let remote_dir = remote_dir.canonicalize()?;
+
+        // The working clone itself, and its .git directory
+        let remote_urls = [
+            remote_dir.display().to_string(),
+            format!("{}/.git", remote_dir.display()),
+        ];
+        for (i, remote_url) in remote_urls.iter().enumerate() {
+            let (repo_dir, repo) = test_helpers::create_main_repo(&temp, &format!("old-{}", i))?;
+            std::fs::create_dir(repo_dir.join("nested"))?;
+            repo.remote("origin", remote_url)?;
+
+            test_helpers::capture_stdout(|| pull_from_file_remote(&repo, remote_url, false))?;
+
+            // The remote URL neither gains nor loses a .git suffix
+            let renamed = Repository::open(temp.path().join("new-name"))?;
+            assert_eq!(&git::get_remote_url(&renamed)?, remote_url);
+            std::fs::remove_dir_all(temp.path().join("new-name"))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_ssh_remote() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        for (local_name, remote_url) in [
+            (
+                "ssh-url",
+                "ssh://git@git.example.com/srv/git/new-ssh-url.git",
+            ),
+            ("scp-like", "git@git.example.com:new-scp-like.git"),
+        ] {
+            let (repo_dir, repo) = test_helpers::create_main_repo(&temp, local_name)?;
+            repo.remote("origin", remote_url)?;
+            std::env::set_current_dir(&repo_dir)?;
+
+            let (output, changed) =
+                test_helpers::capture_stdout(|| pull_from_file_remote(&repo, remote_url, false))?;
+            assert!(changed);
+            assert!(
+                !output.contains("remote from"),
+                "Remote URL shouldn't change: {}",
+                output
+            );
+
+            let new_dir = temp.path().join(format!("new-{}", local_name));
+            assert!(new_dir.exists(), "Expected {} to exist", new_dir.display());
+            let renamed = Repository::open(&new_dir)?;
+            assert_eq!(git::get_remote_url(&renamed)?, remote_url);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_pull_invalid_remote_path() -> anyhow::Result<()> {
         let temp = assert_fs::TempDir::new()?;
@@ -537,6 +848,63 @@ mod tests {
             git::get_remote_url(&push_test_setup.repo)?
         );
 
+        // The working copy keeps its name and is now in line with the remote
+        test_helpers::assert_directory_existence(&push_test_setup.temp, "new-name", true)?;
+        let changed = test_helpers::capture_stdout(|| {
+            pull_from_file_remote(&push_test_setup.repo, &expected_new_url, true)
+        })?
+        .1;
+        assert!(!changed, "Pull after push should have nothing to do");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_rename_non_bare_remote() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("unused.git", "new-name")?;
+        std::fs::create_dir(push_test_setup.temp.path().join("remotes"))?;
+        let (remote_dir, _remote_repo) =
+            test_helpers::create_main_repo(&push_test_setup.temp, "remotes/old-name")?;
+        let remote_url = test_helpers::get_canonical_remote_url(&remote_dir)?;
+        push_test_setup.repo.remote("origin", &remote_url)?;
+
+        test_helpers::capture_stdout(|| {
+            push_to_file_remote(&push_test_setup.repo, &remote_url, false)
+        })?;
+
+        let temp = &push_test_setup.temp;
+        test_helpers::assert_directory_existence(temp, "remotes/old-name", false)?;
+        test_helpers::assert_directory_existence(temp, "remotes/new-name", true)?;
+        test_helpers::assert_directory_existence(temp, "remotes/new-name.git", false)?;
+        assert_eq!(
+            remote_url.replace("old-name", "new-name"),
+            git::get_remote_url(&push_test_setup.repo)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_rename_working_clone_git_dir() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("unused.git", "new-name")?;
+        std::fs::create_dir(push_test_setup.temp.path().join("remotes"))?;
+        let (remote_dir, _remote_repo) =
+            test_helpers::create_main_repo(&push_test_setup.temp, "remotes/old-name")?;
+        let remote_url = format!("{}/.git", remote_dir.canonicalize()?.display());
+        push_test_setup.repo.remote("origin", &remote_url)?;
+
+        test_helpers::capture_stdout(|| {
+            push_to_file_remote(&push_test_setup.repo, &remote_url, false)
+        })?;
+
+        let temp = &push_test_setup.temp;
+        test_helpers::assert_directory_existence(temp, "remotes/old-name", false)?;
+        test_helpers::assert_directory_existence(temp, "remotes/new-name/.git", true)?;
+        assert_eq!(
+            remote_url.replace("old-name", "new-name"),
+            git::get_remote_url(&push_test_setup.repo)?
+        );
+
         Ok(())
     }
 
@@ -561,4 +929,19 @@ mod tests {
             Ok(_) => panic!("Expected error, but operation succeeded"),
         }
     }
+
+    #[test]
+    fn test_push_ssh_remote() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("existing-repo.git", "local-repo")?;
+        let remote_url = "git@git.example.com:existing-repo.git";
+
+        let result = push_to_file_remote(&push_test_setup.repo, remote_url, false);
+        assert!(
+            matches!(&result, Err(Error::Fs(msg)) if msg.contains("over SSH")),
+            "Expected an SSH error, got: {:?}",
+            result
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,57 @@
+use crate::{
+    git,
+    remotes::{
+        file::{operations, url},
+        RemoteProvider, ResolvedRepo,
+    },
+    types::{PushOptions, RemoteKind, Result},
+    utils::fs,
+};
+use git2::Repository;
+use std::path::Path;
+
+pub struct FileProvider {
+    /// Resolve names from the URL alone, without touching the filesystem.
+    pub offline: bool,
+}
+
+impl RemoteProvider for FileProvider {
+    fn kind(&self) -> RemoteKind {
+        RemoteKind::File
+    }
+
+    fn resolve_repo_name(&self, remote_url: &str) -> Result<ResolvedRepo> {
+        // A remote path over SSH can't be canonicalized locally; take the name as written
+        if self.offline || url::ssh_remote_path(remote_url).is_some() {
+            let path = url::ssh_remote_path(remote_url).unwrap_or(remote_url);
+            let name = git::extract_repo_name_from_path(path.trim_end_matches('/'))?;
+            return Ok(ResolvedRepo {
+                full_name: name.clone(),
+                name,
+                location: remote_url.to_string(),
+                moved_from: None,
+            });
+        }
+
+        let canonical_path = fs::resolve_canonical_path(Path::new(remote_url))?;
+        let name = git::extract_repo_name_from_path(&canonical_path)?;
+
+        Ok(ResolvedRepo {
+            full_name: name.clone(),
+            name,
+            location: canonical_path,
+            moved_from: None,
+        })
+    }
+
+    fn format_remote_url(&self, remote_url: &str, resolved: &ResolvedRepo) -> Result<String> {
+        if self.offline || url::ssh_remote_path(remote_url).is_some() {
+            return Ok(remote_url.to_string());
+        }
+        url::format_new_remote_url(remote_url, &resolved.location)
+    }
+
+    fn push(&self, repo: &Repository, remote_url: &str, options: &PushOptions) -> Result<()> {
+        operations::push_to_file_remote(repo, remote_url, options.dry_run)
+    }
+}
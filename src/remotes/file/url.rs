@@ -1,8 +1,32 @@
 use crate::types::{Error, Result};
 use path_clean::PathClean;
+use regex::Regex;
 use std::path::Path;
 
+/// The path portion of a repository reached over SSH, either
+/// `ssh://[user@]host[:port]/path` or the scp-like `[user@]host:path`.
+/// Returns None for local paths, including Windows drive paths like `C:\repo`.
+pub fn ssh_remote_path(remote_url: &str) -> Option<&str> {
+    if let Some(rest) = remote_url.strip_prefix("ssh://") {
+        let re = Regex::new(r"^(?:[^@/\s]+@)?[^/:\s]+(?::\d+)?(/\S+)$").unwrap();
+        return re.captures(rest)?.get(1).map(|m| m.as_str());
+    }
+    if remote_url.contains("://") {
+        return None;
+    }
+
+    // A single-letter host would be a Windows drive letter
+    let re = Regex::new(r"^(?:[^@/:\s]+@)?[^/:\\\s]{2,}:(\S+)$").unwrap();
+    re.captures(remote_url)?.get(1).map(|m| m.as_str())
+}
+
 /// Formats a new path from a canonical path, keeping the format of the original remote URL.
+/// Whether a file remote URL is a relative path, with or without `file://`.
+pub fn is_relative(remote_url: &str) -> bool {
+    let path = remote_url.trim_start_matches("file://");
+    ssh_remote_path(remote_url).is_none() && !path.starts_with('~') && Path::new(path).is_relative()
+}
+
 pub fn format_new_remote_url(original_remote_url: &str, canonical_path: &str) -> Result<String> {
     // If the original URL is relative and it is equivalent to the given canonical_path (without canonicalization),
     // then just return the original URL.
@@ -31,6 +55,16 @@ pub fn format_new_remote_url(original_remote_url: &str, canonical_path: &str) ->
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_relative() {
+        assert!(is_relative("../repo.git"));
+        assert!(is_relative("file://../repo.git"));
+        assert!(!is_relative("/path/repo.git"));
+        assert!(!is_relative("file:///path/repo.git"));
+        assert!(!is_relative("~/repo.git"));
+        assert!(!is_relative("git@host.example:repo.git"));
+    }
+
     #[test]
     fn test_format_new_remote_url() -> anyhow::Result<()> {
         // Calculate canonical path for relative path test
@@ -70,4 +104,29 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_ssh_remote_path() {
+        let test_cases = vec![
+            (
+                "ssh://user@host/srv/git/repo.git",
+                Some("/srv/git/repo.git"),
+            ),
+            (
+                "ssh://host:2222/srv/git/repo.git",
+                Some("/srv/git/repo.git"),
+            ),
+            ("user@host:repo.git", Some("repo.git")),
+            ("host:/srv/git/repo.git", Some("/srv/git/repo.git")),
+            ("/srv/git/repo.git", None),
+            ("file:///srv/git/repo.git", None),
+            ("../repo.git", None),
+            ("C:\\repos\\repo.git", None),
+            ("C:/repos/repo.git", None),
+        ];
+
+        for (url, expected) in test_cases {
+            assert_eq!(ssh_remote_path(url), expected, "{}", url);
+        }
+    }
 }
@@ -0,0 +1,121 @@
+use crate::{
+    config::{self, ForgeProvider},
+    types::{Error, Result},
+};
+use log::debug;
+use reqwest::blocking::Client as ReqwestClient;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GiteaRepo {
+    pub name: String,
+    pub full_name: String,
+    pub clone_url: String,
+}
+
+fn api_error(forge: &ForgeProvider, message: impl Into<String>) -> Error {
+    Error::ForgeApi(forge.name.clone(), message.into())
+}
+
+fn create_client(forge: &ForgeProvider) -> Result<ReqwestClient> {
+    let mut headers = HeaderMap::new();
+
+    if let Some(token) = &forge.token {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("token {}", token))
+                .map_err(|e| api_error(forge, e.to_string()))?,
+        );
+    }
+
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_str(&config::current().get_user_agent())
+            .map_err(|e| Error::Config(format!("Invalid user agent: {}", e)))?,
+    );
+
+    ReqwestClient::builder()
+        .default_headers(headers)
+        .timeout(config::current().get_http_timeout()?)
+        .build()
+        .map_err(|e| api_error(forge, e.to_string()))
+}
+
+/// The REST endpoint for a repository on the forge, `{api_base}/repos/{owner}/{repo}`.
+pub fn repos_url(forge: &ForgeProvider, owner: &str, repo: &str) -> String {
+    format!("{}/repos/{}/{}", forge.api_base, owner, repo)
+}
+
+/// `GET /repos/{owner}/{repo}`. Like GitHub, Gitea follows renames and transfers,
+/// so `full_name` is the repository's current location.
+pub fn get_repo(forge: &ForgeProvider, owner: &str, repo: &str) -> Result<GiteaRepo> {
+    let url = repos_url(forge, owner, repo);
+    debug!("GET {}", url);
+    let resp = create_client(forge)?
+        .get(&url)
+        .send()
+        .map_err(|e| api_error(forge, e.to_string()))?;
+
+    match resp.status() {
+        StatusCode::OK => resp.json().map_err(|e| api_error(forge, e.to_string())),
+        StatusCode::UNAUTHORIZED => Err(api_error(
+            forge,
+            format!(
+                "Authentication failed: check the token in the [provider.{}] section",
+                forge.name
+            ),
+        )),
+        StatusCode::NOT_FOUND => Err(api_error(
+            forge,
+            format!(
+                "Repository {}/{} not found. If it is private, add a token to the [provider.{}] section",
+                owner, repo, forge.name
+            ),
+        )),
+        status => Err(api_error(
+            forge,
+            format!("Failed to get repository: {}", status),
+        )),
+    }
+}
+
+/// `PATCH /repos/{owner}/{repo}` with the new name.
+pub fn update_repo_name(
+    forge: &ForgeProvider,
+    owner: &str,
+    repo: &str,
+    new_name: &str,
+) -> Result<GiteaRepo> {
+    let url = repos_url(forge, owner, repo);
+    debug!("PATCH {}", url);
+    let resp = create_client(forge)?
+        .patch(&url)
+        .json(&json!({ "name": new_name }))
+        .send()
+        .map_err(|e| api_error(forge, e.to_string()))?;
+
+    match resp.status() {
+        StatusCode::OK => resp.json().map_err(|e| api_error(forge, e.to_string())),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(api_error(
+            forge,
+            format!(
+                "Permission denied: the token in the [provider.{}] section can't rename {}/{}",
+                forge.name, owner, repo
+            ),
+        )),
+        StatusCode::UNPROCESSABLE_ENTITY => Err(api_error(
+            forge,
+            format!(
+                "Cannot rename repository to '{}'. The name may be taken or invalid.",
+                new_name
+            ),
+        )),
+        status => Err(api_error(
+            forge,
+            format!("Failed to update repository name: {}", status),
+        )),
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    config::ForgeProvider,
+    remotes::{
+        self,
+        gitea::{client, url},
+        RemoteProvider, ResolvedRepo,
+    },
+    types::{Error, PushOptions, RemoteKind, Result},
+};
+use git2::Repository;
+use log::debug;
+
+/// Remotes on a self-hosted Gitea instance declared in a `[provider.NAME]` section.
+pub struct GiteaProvider {
+    pub forge: ForgeProvider,
+}
+
+impl GiteaProvider {
+    fn parse_url(&self, remote_url: &str) -> Result<(String, String)> {
+        url::parse_forge_url(remote_url)
+            .map(|(_, owner, repo)| (owner, repo))
+            .ok_or_else(|| Error::InvalidRemoteUrl(remote_url.to_string()))
+    }
+}
+
+impl RemoteProvider for GiteaProvider {
+    fn kind(&self) -> RemoteKind {
+        RemoteKind::Gitea
+    }
+
+    fn resolve_repo_name(&self, remote_url: &str) -> Result<ResolvedRepo> {
+        let (owner, repo) = self.parse_url(remote_url)?;
+        let repo_info = client::get_repo(&self.forge, &owner, &repo)?;
+
+        let requested_full_name = format!("{}/{}", owner, repo);
+        let moved_from =
+            (repo_info.full_name != requested_full_name).then_some(requested_full_name);
+
+        Ok(ResolvedRepo {
+            name: repo_info.name,
+            full_name: repo_info.full_name,
+            location: repo_info.clone_url,
+            moved_from,
+        })
+    }
+
+    fn format_remote_url(&self, remote_url: &str, resolved: &ResolvedRepo) -> Result<String> {
+        let owner = resolved.full_name.split('/').next().unwrap_or_default();
+        Ok(url::format_new_remote_url(
+            remote_url,
+            owner,
+            &resolved.name,
+        ))
+    }
+
+    fn push(&self, repo: &Repository, remote_url: &str, options: &PushOptions) -> Result<()> {
+        let target_name = remotes::push_target_name(repo, options)?;
+        let (owner, remote_repo_name) = self.parse_url(remote_url)?;
+        debug!(
+            "Pushing '{}' to {}/{} on {}",
+            target_name, owner, remote_repo_name, self.forge.name
+        );
+
+        if remote_repo_name == target_name {
+            println!("Repository name already matches the local directory name");
+            return Ok(());
+        }
+
+        if options.dry_run {
+            println!(
+                "Would update {} repository name from '{}' to '{}'",
+                self.forge.name, remote_repo_name, target_name
+            );
+            let new_remote_url = url::format_new_remote_url(remote_url, &owner, &target_name);
+            return remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, true);
+        }
+
+        let updated =
+            client::update_repo_name(&self.forge, &owner, &remote_repo_name, &target_name)?;
+        let resolved_owner = updated.full_name.split('/').next().unwrap_or(&owner);
+        let new_remote_url = url::format_new_remote_url(remote_url, resolved_owner, &updated.name);
+        remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{self, Config, ForgeKind},
+        git,
+        progress::TextSink,
+        remotes, test_helpers,
+        types::PullOptions,
+    };
+    use mockito::Matcher;
+
+    #[test]
+    fn test_configured_gitea_provider() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let mut server = mockito::Server::new();
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_provider(ForgeProvider {
+            name: "mygitea".to_string(),
+            host: "git.example.com".to_string(),
+            api_base: format!("{}/api/v1", server.url()),
+            kind: ForgeKind::Gitea,
+            token: Some("gitea-token".to_string()),
+        })?;
+
+        let repo_json = |name: &str| {
+            serde_json::json!({
+                "name": name,
+                "full_name": format!("owner/{}", name),
+                "clone_url": format!("https://git.example.com/owner/{}.git", name),
+            })
+            .to_string()
+        };
+        let get_mock = server
+            .mock("GET", "/api/v1/repos/owner/old-name")
+            .match_header("authorization", "token gitea-token")
+            .with_body(repo_json("new-name"))
+            .create();
+        let patch_mock = server
+            .mock("PATCH", "/api/v1/repos/owner/new-name")
+            .match_body(Matcher::Json(serde_json::json!({ "name": "renamed" })))
+            .with_body(repo_json("renamed"))
+            .create();
+
+        let (_, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        let remote_url = "https://git.example.com/owner/old-name.git";
+        repo.remote("origin", remote_url)?;
+
+        let pushed = config::with_config(&config, || {
+            let provider = remotes::provider_for_url(remote_url, None, false);
+            assert_eq!(provider.kind(), RemoteKind::Gitea);
+            test_helpers::capture_stdout(|| {
+                remotes::pull(
+                    provider.as_ref(),
+                    &repo,
+                    remote_url,
+                    &PullOptions::default(),
+                    &TextSink,
+                )
+            })?;
+
+            // Rename locally, then push the new name back
+            std::fs::rename(temp.path().join("new-name"), temp.path().join("renamed"))?;
+            let repo = Repository::open(temp.path().join("renamed"))?;
+            let remote_url = git::get_remote_url(&repo)?;
+            test_helpers::capture_stdout(|| {
+                provider.push(&repo, &remote_url, &PushOptions::default())
+            })?;
+            anyhow::Ok(git::get_remote_url(&repo)?)
+        });
+
+        assert_eq!(pushed?, "https://git.example.com/owner/renamed.git");
+        get_mock.assert();
+        patch_mock.assert();
+
+        Ok(())
+    }
+}
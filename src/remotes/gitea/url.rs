@@ -0,0 +1,61 @@
+use regex::Regex;
+
+/// Splits an `https://`, `ssh://` or scp-like `user@host:` remote URL into
+/// `(host, owner, repo)`, without the `.git` suffix.
+pub fn parse_forge_url(url: &str) -> Option<(String, String, String)> {
+    let re = Regex::new(
+        r"^(?:(?:https?|ssh|git)://(?:[^@/]+@)?([^/:]+)(?::\d+)?/|[^@/\s]+@([^:/\s]+):)([^/]+)/([^/]+?)(?:\.git)?/?$",
+    )
+    .unwrap();
+    let caps = re.captures(url)?;
+    let host = caps.get(1).or_else(|| caps.get(2))?.as_str();
+    Some((host.to_string(), caps[3].to_string(), caps[4].to_string()))
+}
+
+/// Points `url` at `owner/repo`, keeping its scheme, host and `.git` suffix.
+pub fn format_new_remote_url(url: &str, owner: &str, repo: &str) -> String {
+    let re = Regex::new(r"^(.*[/:])[^/:]+/[^/]+?((?:\.git)?/?)$").unwrap();
+    re.replace(url, |caps: &regex::Captures| {
+        format!("{}{}/{}{}", &caps[1], owner, repo, &caps[2])
+    })
+    .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_forge_url() {
+        let cases = [
+            (
+                "https://git.example.com/owner/old-name.git",
+                "https://git.example.com/owner/new-name.git",
+            ),
+            (
+                "git@git.example.com:owner/old-name.git",
+                "git@git.example.com:owner/new-name.git",
+            ),
+            (
+                "ssh://git@git.example.com:2222/owner/old-name",
+                "ssh://git@git.example.com:2222/owner/new-name",
+            ),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                parse_forge_url(url),
+                Some((
+                    "git.example.com".to_string(),
+                    "owner".to_string(),
+                    "old-name".to_string()
+                )),
+                "{}",
+                url
+            );
+            assert_eq!(format_new_remote_url(url, "owner", "new-name"), expected);
+        }
+
+        assert_eq!(parse_forge_url("/srv/git/repo.git"), None);
+    }
+}
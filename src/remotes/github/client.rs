@@ -1,27 +1,95 @@
 use crate::{
-    config::CONFIG,
-    types::{Error, Result},
+    config,
+    types::{DenialReason, Error, Result},
 };
+use log::{debug, trace};
+use once_cell::sync::Lazy;
 use reqwest::blocking::Client as ReqwestClient;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
 use reqwest::StatusCode;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize)]
+/// Upper bound for establishing a connection, on top of the overall request timeout.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `(api base url, owner, repo)`
+type RepoKey = (String, String, String);
+
+/// Successful repository lookups made during this run.
+static REPO_INFO_CACHE: Lazy<Mutex<HashMap<RepoKey, GitHubRepo>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct GitHubRepo {
     pub name: String,
     pub full_name: String,
     pub clone_url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub has_pages: bool,
+    #[serde(default)]
+    pub forks_count: u64,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub owner: Option<GitHubOwner>,
+    /// Archived repositories are read-only and can't be renamed.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubOwner {
+    pub login: String,
+    /// "User" or "Organization".
+    #[serde(rename = "type")]
+    pub owner_type: String,
 }
 
+impl GitHubRepo {
+    pub fn is_org_owned(&self) -> bool {
+        self.owner
+            .as_ref()
+            .is_some_and(|owner| owner.owner_type == "Organization")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+/// The GitHub API endpoint: `GITHUB_API_BASE_URL`, then the `api-base-url` config
+/// key, then api.github.com. Trailing slashes are dropped so endpoints can be
+/// appended with a single `/`.
 pub fn get_base_url() -> String {
-    std::env::var("GITHUB_API_BASE_URL").unwrap_or_else(|_| "https://api.github.com".to_string())
+    std::env::var("GITHUB_API_BASE_URL")
+        .ok()
+        .or_else(|| config::current().get_api_base_url())
+        .map(|base| base.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| "https://api.github.com".to_string())
+}
+
+/// The REST endpoint for a repository, `{base}/repos/{owner}/{repo}`. Every
+/// repository request builds its URL here.
+pub fn repos_url(owner: &str, repo: &str) -> String {
+    join_repos_url(&get_base_url(), owner, repo)
+}
+
+fn join_repos_url(base: &str, owner: &str, repo: &str) -> String {
+    format!("{}/repos/{}/{}", base.trim_end_matches('/'), owner, repo)
 }
 
 pub fn create_client() -> Result<ReqwestClient> {
     let mut headers = HeaderMap::new();
-    let auth_token = CONFIG.get_github_token().ok();
+    let auth_token = config::current().get_github_token().ok();
 
     // Add authorization header only if token is provided
     if let Some(token_str) = auth_token {
@@ -32,51 +100,320 @@ pub fn create_client() -> Result<ReqwestClient> {
         );
     }
 
-    headers.insert(USER_AGENT, HeaderValue::from_static("git-repo-name"));
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_str(&config::current().get_user_agent())
+            .map_err(|e| Error::Config(format!("Invalid user agent: {}", e)))?,
+    );
 
+    let timeout = config::current().get_http_timeout()?;
     ReqwestClient::builder()
         .default_headers(headers)
+        .timeout(timeout)
+        .connect_timeout(timeout.min(CONNECT_TIMEOUT))
         .build()
         .map_err(|e| Error::GitHubApi(e.to_string()))
 }
 
+/// Error for a request that could not be completed, calling out timeouts so a
+/// hung connection isn't mistaken for an API failure.
+fn request_failed(e: reqwest::Error) -> Error {
+    if e.is_timeout() {
+        Error::GitHubApi(format!(
+            "Request to GitHub timed out ({}). Increase the limit with 'git-repo-name config http-timeout SECONDS'",
+            e
+        ))
+    } else {
+        Error::GitHubApi(e.to_string())
+    }
+}
+
+/// Returns the user the configured token belongs to.
+pub fn get_authenticated_user() -> Result<GitHubUser> {
+    let url = format!("{}/user", get_base_url());
+    let client = create_client()?;
+    debug!("GET {}", url);
+    let response = client.get(&url).send();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK => resp.json().map_err(request_failed),
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            _ => Err(Error::GitHubApi(format!(
+                "Failed to get the authenticated user: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+/// Checks that the configured token is accepted before anything is changed, so a
+/// stale token fails fast instead of partway through a push. Does nothing without
+/// a token. Only a rejected token is an error: other failures (e.g. a proxy that
+/// blocks `/user`) are left for the actual requests to report.
+pub fn verify_token() -> Result<()> {
+    if config::current().get_github_token().is_err() {
+        return Ok(());
+    }
+
+    match get_authenticated_user() {
+        Ok(user) => {
+            debug!("Token belongs to {}", user.login);
+            Ok(())
+        }
+        Err(Error::AuthenticationFailed) => Err(Error::AuthenticationFailed),
+        Err(e) => {
+            debug!("Could not verify the GitHub token: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// The web host OAuth endpoints live on: `GITHUB_OAUTH_BASE_URL`, then the host
+/// behind the configured API endpoint.
+pub fn get_oauth_base_url() -> String {
+    std::env::var("GITHUB_OAUTH_BASE_URL").unwrap_or_else(|_| {
+        format!(
+            "https://{}",
+            crate::remotes::github::url::configured_github_host()
+        )
+    })
+}
+
+/// A code the user enters at `verification_uri` to authorize the device flow.
+#[derive(Debug, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until the codes expire.
+    pub expires_in: u64,
+    /// Minimum number of seconds between polls of the token endpoint.
+    pub interval: u64,
+}
+
+/// The result of polling the token endpoint during the device flow.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceTokenPoll {
+    /// The user hasn't entered the code yet.
+    Pending,
+    /// Polling too often; wait this many seconds between polls from now on.
+    SlowDown(Option<u64>),
+    Token(String),
+}
+
+#[derive(Deserialize)]
+struct DeviceTokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    interval: Option<u64>,
+}
+
+/// Starts the OAuth device flow for the given OAuth app.
+pub fn request_device_code(client_id: &str) -> Result<DeviceCode> {
+    let url = format!("{}/login/device/code", get_oauth_base_url());
+    let client = create_client()?;
+    debug!("POST {}", url);
+    client
+        .post(&url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[("client_id", client_id), ("scope", "repo")])
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .and_then(|resp| resp.json())
+        .map_err(|e| Error::GitHubApi(format!("Failed to start device login: {}", e)))
+}
+
+/// Asks whether the user has authorized the device code yet.
+pub fn poll_device_token(client_id: &str, device_code: &str) -> Result<DeviceTokenPoll> {
+    let url = format!("{}/login/oauth/access_token", get_oauth_base_url());
+    let client = create_client()?;
+    debug!("POST {}", url);
+    let response: DeviceTokenResponse = client
+        .post(&url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("client_id", client_id),
+            ("device_code", device_code),
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ])
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .and_then(|resp| resp.json())
+        .map_err(request_failed)?;
+
+    match (response.access_token, response.error.as_deref()) {
+        (Some(token), _) => Ok(DeviceTokenPoll::Token(token)),
+        (None, Some("authorization_pending")) => Ok(DeviceTokenPoll::Pending),
+        (None, Some("slow_down")) => Ok(DeviceTokenPoll::SlowDown(response.interval)),
+        (None, error) => Err(Error::GitHubApi(format!(
+            "Device login failed: {}",
+            response
+                .error_description
+                .or(error.map(String::from))
+                .unwrap_or_else(|| "no token in the response".to_string())
+        ))),
+    }
+}
+
+/// Detects GitHub's rate limits, which it reports as 403 (or 429) just like
+/// permission problems. The primary limit sets `x-ratelimit-remaining: 0`; the
+/// secondary ("abuse detection") limit says so in the message and usually sends
+/// `Retry-After`.
+fn rate_limit_error(headers: &HeaderMap, body: &str) -> Option<Error> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let exhausted = header("x-ratelimit-remaining") == Some("0");
+    let body = body.to_lowercase();
+    if !exhausted && !body.contains("rate limit") && !body.contains("abuse") {
+        return None;
+    }
+
+    let retry_after = header("retry-after")
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .map(Duration::from_secs)
+        .or_else(|| {
+            // x-ratelimit-reset is the epoch second the primary limit resets at
+            let reset: u64 = header("x-ratelimit-reset")?.trim().parse().ok()?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?;
+            Some(Duration::from_secs(reset.saturating_sub(now.as_secs())))
+        });
+    debug!("Rate limited by GitHub, retry after {:?}", retry_after);
+
+    Some(Error::RateLimited { retry_after })
+}
+
+/// Error for a 403 response on a write. GitHub uses 403 for several unrelated
+/// problems, which its response headers and message tell apart.
+fn permission_denied(resp: reqwest::blocking::Response) -> Error {
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let sso = header("x-github-sso");
+    let accepted_permissions = header("x-accepted-github-permissions");
+    let headers = resp.headers().clone();
+    let body = resp.text().unwrap_or_default();
+
+    if let Some(error) = rate_limit_error(&headers, &body) {
+        return error;
+    }
+
+    if let Some(sso) = sso {
+        // e.g. "required; url=https://github.com/orgs/acme/sso?authorization_request=..."
+        let url = sso
+            .split(';')
+            .find_map(|part| part.trim().strip_prefix("url="))
+            .map(|url| format!(" at {}", url))
+            .unwrap_or_default();
+        return Error::PermissionDenied {
+            reason: DenialReason::SsoRequired,
+            message: format!(
+                "Permission denied. Your GitHub token is not authorized for this organization's SAML single sign-on. Authorize it{}.",
+                url
+            ),
+        };
+    }
+
+    if body.to_lowercase().contains("expired") {
+        return Error::PermissionDenied {
+            reason: DenialReason::TokenExpired,
+            message: "Permission denied. Your GitHub token has expired. Update it with 'git-repo-name config github-token YOUR_TOKEN'".to_string(),
+        };
+    }
+
+    let message = match accepted_permissions.filter(|p| !p.is_empty()) {
+        Some(permissions) => format!(
+            "Permission denied. Your GitHub token is missing a required permission; GitHub accepts: {}.",
+            permissions
+        ),
+        None => "Permission denied. Ensure your GitHub token has the 'Administration' repository permission (write).".to_string(),
+    };
+    Error::PermissionDenied {
+        reason: DenialReason::MissingPermission,
+        message,
+    }
+}
+
+/// Looks up a repository, reusing an earlier response for the same repository
+/// within this run.
 pub fn get_repo_info(owner: &str, repo: &str) -> Result<GitHubRepo> {
-    let url = format!("{}/repos/{}/{}", get_base_url(), owner, repo);
+    let key = (get_base_url(), owner.to_string(), repo.to_string());
+    if let Some(cached) = REPO_INFO_CACHE.lock().unwrap().get(&key) {
+        trace!("Using cached repository info for {}/{}", owner, repo);
+        return Ok(cached.clone());
+    }
+
+    // GitHub returns 404 for private repos when unauthorized
+    let repo_info = find_repo_info(owner, repo)?.ok_or_else(|| {
+        Error::GitHubApi(
+            "Repository not found. If this is a private repository, please configure a GitHub token with 'git-repo-name config github-token YOUR_TOKEN'".to_string(),
+        )
+    })?;
+
+    REPO_INFO_CACHE
+        .lock()
+        .unwrap()
+        .insert(key, repo_info.clone());
+    Ok(repo_info)
+}
+
+/// Forgets cached repository lookups. Called after any write, and by tests whose
+/// mock responses change between cases.
+pub fn clear_repo_info_cache() {
+    REPO_INFO_CACHE.lock().unwrap().clear();
+}
+
+/// Looks up a repository, returning None when GitHub responds with 404.
+pub fn find_repo_info(owner: &str, repo: &str) -> Result<Option<GitHubRepo>> {
+    let url = repos_url(owner, repo);
     let client = create_client()?;
+    debug!("GET {}", url);
     let response = client.get(&url).send();
 
     match response {
         Ok(resp) => {
-            if resp.status() == StatusCode::NOT_FOUND {
-                // GitHub returns 404 for private repos when unauthorized
-                Err(Error::GitHubApi(
-                  "Repository not found. If this is a private repository, please configure a GitHub token with 'git-repo-name config github-token YOUR_TOKEN'".to_string(),
-              ))
+            if resp.status() == StatusCode::UNAUTHORIZED {
+                Err(Error::AuthenticationFailed)
+            } else if resp.status() == StatusCode::NOT_FOUND {
+                Ok(None)
+            } else if resp.status() == StatusCode::FORBIDDEN
+                || resp.status() == StatusCode::TOO_MANY_REQUESTS
+            {
+                let status = resp.status();
+                let headers = resp.headers().clone();
+                let body = resp.text().unwrap_or_default();
+                Err(rate_limit_error(&headers, &body).unwrap_or_else(|| {
+                    Error::GitHubApi(format!("Failed to get repository: {}", status))
+                }))
             } else {
                 // Process successful response
                 match resp.error_for_status() {
-                    Ok(resp) => resp.json().map_err(|e| Error::GitHubApi(e.to_string())),
-                    Err(e) => Err(Error::GitHubApi(e.to_string())),
+                    Ok(resp) => resp.json().map(Some).map_err(request_failed),
+                    Err(e) => Err(request_failed(e)),
                 }
             }
         }
-        Err(e) => Err(Error::GitHubApi(e.to_string())),
+        Err(e) => Err(request_failed(e)),
     }
 }
 
 pub fn update_repo_name(owner: &str, repo: &str, new_name: &str) -> Result<GitHubRepo> {
-    let url = format!("{}/repos/{}/{}", get_base_url(), owner, repo);
+    let url = repos_url(owner, repo);
     let client = create_client()?;
     let response = client.patch(&url).json(&json!({ "name": new_name })).send();
+    clear_repo_info_cache();
 
     match response {
         Ok(resp) => match resp.status() {
-            StatusCode::OK | StatusCode::CREATED => {
-                resp.json().map_err(|e| Error::GitHubApi(e.to_string()))
-            }
-            StatusCode::FORBIDDEN => Err(Error::GitHubApi(
-                "Permission denied. Ensure your GitHub token has the 'Administration' repository permission (write).".to_string(),
-            )),
+            StatusCode::OK | StatusCode::CREATED => resp.json().map_err(request_failed),
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => Err(permission_denied(resp)),
             StatusCode::UNPROCESSABLE_ENTITY => Err(Error::GitHubApi(format!(
                 "Cannot rename repository to '{}'. The name may be taken or invalid.",
                 new_name
@@ -86,17 +423,242 @@ pub fn update_repo_name(owner: &str, repo: &str, new_name: &str) -> Result<GitHu
                 resp.status()
             ))),
         },
-        Err(e) => Err(Error::GitHubApi(e.to_string())),
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+/// The GraphQL endpoint next to the REST one: `{base}/graphql`, or `/api/graphql`
+/// for GitHub Enterprise's `/api/v3`.
+pub(crate) fn graphql_url() -> String {
+    let base = get_base_url();
+    format!("{}/graphql", base.strip_suffix("/v3").unwrap_or(&base))
+}
+
+#[derive(Deserialize)]
+struct GraphqlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+/// Runs a GraphQL query. GitHub reports most failures as a 200 with `errors`.
+fn graphql<T: DeserializeOwned>(query: &str, variables: serde_json::Value) -> Result<T> {
+    let url = graphql_url();
+    let client = create_client()?;
+    debug!("POST {}", url);
+    let response = client
+        .post(&url)
+        .json(&json!({ "query": query, "variables": variables }))
+        .send();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK => {
+                let body: GraphqlResponse<T> = resp.json().map_err(request_failed)?;
+                match (body.data, body.errors.into_iter().next()) {
+                    (_, Some(error)) => Err(Error::GitHubApi(error.message)),
+                    (Some(data), None) => Ok(data),
+                    (None, None) => Err(Error::GitHubApi("Empty GraphQL response".to_string())),
+                }
+            }
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => Err(permission_denied(resp)),
+            _ => Err(Error::GitHubApi(format!(
+                "GraphQL request failed: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlRepository {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    name_with_owner: String,
+    #[serde(default)]
+    url: String,
+}
+
+/// Renames a repository with the GraphQL `updateRepository` mutation instead of
+/// the REST API (see the `github-api` config key).
+pub fn update_repo_name_graphql(owner: &str, repo: &str, new_name: &str) -> Result<GitHubRepo> {
+    #[derive(Deserialize)]
+    struct Lookup {
+        repository: GraphqlRepository,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Update {
+        update_repository: Lookup,
+    }
+
+    // The mutation takes the repository's node ID rather than owner/name
+    let lookup: Lookup = graphql(
+        "query($owner: String!, $name: String!) { repository(owner: $owner, name: $name) { id } }",
+        json!({ "owner": owner, "name": repo }),
+    )?;
+    let update: Update = graphql(
+        "mutation($id: ID!, $name: String!) { updateRepository(input: {repositoryId: $id, name: $name}) { repository { name nameWithOwner url } } }",
+        json!({ "id": lookup.repository.id, "name": new_name }),
+    )?;
+    clear_repo_info_cache();
+
+    let repository = update.update_repository.repository;
+    Ok(GitHubRepo {
+        name: repository.name,
+        full_name: repository.name_with_owner,
+        clone_url: format!("{}.git", repository.url),
+        description: None,
+        topics: Vec::new(),
+        has_pages: false,
+        forks_count: 0,
+        default_branch: None,
+        owner: None,
+        archived: false,
+    })
+}
+
+/// Starts transferring a repository to another owner, optionally renaming it.
+/// GitHub accepts transfers asynchronously (202), so the repository may not be
+/// reachable at its new location right away.
+pub fn transfer_repo(
+    owner: &str,
+    repo: &str,
+    new_owner: &str,
+    new_name: &str,
+) -> Result<GitHubRepo> {
+    let url = format!("{}/transfer", repos_url(owner, repo));
+    let client = create_client()?;
+    let response = client
+        .post(&url)
+        .json(&json!({ "new_owner": new_owner, "new_name": new_name }))
+        .send();
+    clear_repo_info_cache();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK | StatusCode::ACCEPTED => {
+                resp.json().map_err(request_failed)
+            }
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => {
+                let headers = resp.headers().clone();
+                let body = resp.text().unwrap_or_default();
+                Err(rate_limit_error(&headers, &body).unwrap_or_else(|| {
+                    Error::PermissionDenied {
+                        reason: DenialReason::MissingPermission,
+                        message: format!(
+                            "Permission denied. Ensure your GitHub token has admin access to '{}/{}' and can create repositories in '{}'.",
+                            owner, repo, new_owner
+                        ),
+                    }
+                }))
+            }
+            StatusCode::UNPROCESSABLE_ENTITY => Err(Error::GitHubApi(format!(
+                "Cannot transfer repository to '{}/{}'. The owner may not exist or already have a repository with that name.",
+                new_owner, new_name
+            ))),
+            _ => Err(Error::GitHubApi(format!(
+                "Failed to transfer repository: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+pub fn update_repo_description(owner: &str, repo: &str, description: &str) -> Result<GitHubRepo> {
+    let url = repos_url(owner, repo);
+    let client = create_client()?;
+    let response = client
+        .patch(&url)
+        .json(&json!({ "description": description }))
+        .send();
+    clear_repo_info_cache();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK => resp.json().map_err(request_failed),
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => Err(permission_denied(resp)),
+            _ => Err(Error::GitHubApi(format!(
+                "Failed to update repository description: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+pub fn update_repo_default_branch(owner: &str, repo: &str, branch: &str) -> Result<GitHubRepo> {
+    let url = repos_url(owner, repo);
+    let client = create_client()?;
+    let response = client
+        .patch(&url)
+        .json(&json!({ "default_branch": branch }))
+        .send();
+    clear_repo_info_cache();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK => resp.json().map_err(request_failed),
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => Err(permission_denied(resp)),
+            StatusCode::UNPROCESSABLE_ENTITY => Err(Error::GitHubApi(format!(
+                "Cannot set the default branch to '{}'. The branch must already exist on GitHub.",
+                branch
+            ))),
+            _ => Err(Error::GitHubApi(format!(
+                "Failed to update repository default branch: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
+    }
+}
+
+pub fn replace_repo_topics(owner: &str, repo: &str, topics: &[String]) -> Result<()> {
+    let url = format!("{}/topics", repos_url(owner, repo));
+    let client = create_client()?;
+    let response = client.put(&url).json(&json!({ "names": topics })).send();
+    clear_repo_info_cache();
+
+    match response {
+        Ok(resp) => match resp.status() {
+            StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => Err(Error::AuthenticationFailed),
+            StatusCode::FORBIDDEN => Err(permission_denied(resp)),
+            StatusCode::UNPROCESSABLE_ENTITY => Err(Error::GitHubApi(format!(
+                "Cannot set repository topics to '{}'. Topics must be lowercase and may only contain letters, numbers and hyphens.",
+                topics.join(", ")
+            ))),
+            _ => Err(Error::GitHubApi(format!(
+                "Failed to update repository topics: {}",
+                resp.status()
+            ))),
+        },
+        Err(e) => Err(request_failed(e)),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{self, Config};
 
     #[test]
     fn test_get_repo_info() -> anyhow::Result<()> {
-        use crate::config::CONFIG;
         use crate::test_helpers;
         use assert_fs::TempDir;
 
@@ -110,10 +672,10 @@ mod tests {
 
         test_helpers::mock_github_get_repo(owner, owner, repo, repo);
 
+        // A fresh config has no token, so requests are unauthenticated
+        let config = Config::load_from(temp.path().join("config"))?;
         {
-            CONFIG.set_github_token("")?;
-
-            let result = get_repo_info(owner, repo);
+            let result = config::with_config(&config, || get_repo_info(owner, repo));
             assert!(
                 result.is_ok(),
                 "Expected success for public repo with unauthenticated request"
@@ -124,7 +686,7 @@ mod tests {
         test_helpers::mock_github_get_repo_error(owner, &private_repo);
 
         {
-            let result = get_repo_info(owner, &private_repo);
+            let result = config::with_config(&config, || get_repo_info(owner, &private_repo));
             assert!(
                 result.is_err(),
                 "Expected error for private repo with unauthenticated request"
@@ -140,4 +702,380 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_update_repo_description() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mock = test_helpers::mock_github_update_repo_description(
+            "owner",
+            "test-repo",
+            "A brand new description",
+        );
+
+        let repo_info = update_repo_description("owner", "test-repo", "A brand new description")?;
+
+        mock.assert();
+        assert_eq!(
+            repo_info.description.as_deref(),
+            Some("A brand new description")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_repo_info_unauthorized() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo_unauthorized("owner", "test-repo");
+
+        match get_repo_info("owner", "test-repo") {
+            Err(e) => assert!(
+                e.to_string()
+                    .contains("Authentication failed: your GitHub token is invalid or expired"),
+                "Expected authentication error, got: {}",
+                e
+            ),
+            Ok(_) => panic!("Expected error for unauthorized request"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_agent_env_override() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        std::env::set_var("GIT_REPO_NAME_USER_AGENT", "acme-proxy-client/1.0");
+
+        let mock = server
+            .mock("GET", "/repos/owner/test-repo")
+            .match_header("user-agent", "acme-proxy-client/1.0")
+            .with_status(200)
+            .with_body(
+                r#"{"name":"test-repo","full_name":"owner/test-repo","clone_url":"https://github.com/owner/test-repo.git"}"#,
+            )
+            .create();
+
+        let result = get_repo_info("owner", "test-repo");
+        std::env::remove_var("GIT_REPO_NAME_USER_AGENT");
+
+        result?;
+        mock.assert();
+        assert_eq!(
+            config::current().get_user_agent(),
+            format!("git-repo-name/{}", env!("CARGO_PKG_VERSION"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_repo_info_is_cached() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let mock = server
+            .mock("GET", "/repos/owner/cached-repo")
+            .with_status(200)
+            .with_body(
+                r#"{"name":"cached-repo","full_name":"owner/cached-repo","clone_url":"https://github.com/owner/cached-repo.git"}"#,
+            )
+            .expect(1)
+            .create();
+
+        let first = get_repo_info("owner", "cached-repo")?;
+        let second = get_repo_info("owner", "cached-repo")?;
+
+        mock.assert();
+        assert_eq!(first.full_name, second.full_name);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_client_uses_token_file() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::prelude::*;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let token_file = temp.child("token");
+        token_file.write_str("file-token\n")?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let mock = server
+            .mock("GET", "/repos/owner/token-file-repo")
+            .match_header("authorization", "token file-token")
+            .with_status(200)
+            .with_body(
+                r#"{"name":"token-file-repo","full_name":"owner/token-file-repo","clone_url":"https://github.com/owner/token-file-repo.git"}"#,
+            )
+            .create();
+
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_github_token_file(&token_file.path().display().to_string())?;
+        let result = config::with_config(&config, || find_repo_info("owner", "token-file-repo"));
+
+        assert!(result?.is_some());
+        mock.assert();
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_timeout() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _mock = server
+            .mock("GET", "/repos/owner/slow-repo")
+            .with_status(200)
+            .with_chunked_body(|writer| {
+                std::thread::sleep(std::time::Duration::from_millis(1500));
+                writer.write_all(b"{}")
+            })
+            .create();
+
+        std::env::set_var("GIT_REPO_NAME_HTTP_TIMEOUT", "0.2");
+        let result = find_repo_info("owner", "slow-repo");
+        std::env::remove_var("GIT_REPO_NAME_HTTP_TIMEOUT");
+
+        match result {
+            Err(e) => assert!(
+                e.to_string().contains("Request to GitHub timed out"),
+                "Expected timeout error, got: {}",
+                e
+            ),
+            Ok(_) => panic!("Expected the request to time out"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_repo_name_forbidden_messages() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let cases = vec![
+            (
+                ("x-github-sso", "required; url=https://github.com/orgs/acme/sso?authorization_request=abc"),
+                r#"{"message":"Resource protected by organization SAML enforcement."}"#,
+                "SAML single sign-on. Authorize it at https://github.com/orgs/acme/sso?authorization_request=abc",
+            ),
+            (
+                ("x-accepted-github-permissions", "administration=write"),
+                r#"{"message":"Resource not accessible by personal access token"}"#,
+                "missing a required permission; GitHub accepts: administration=write",
+            ),
+            (
+                ("x-github-request-id", "1"),
+                r#"{"message":"Token has expired"}"#,
+                "Your GitHub token has expired",
+            ),
+            (
+                ("x-github-request-id", "1"),
+                r#"{"message":"Forbidden"}"#,
+                "'Administration' repository permission (write)",
+            ),
+        ];
+
+        for ((header, value), body, expected) in cases {
+            let mut server = mockito::Server::new();
+            std::env::set_var("GITHUB_API_BASE_URL", server.url());
+            let _mock = server
+                .mock("PATCH", "/repos/owner/test-repo")
+                .with_status(403)
+                .with_header(header, value)
+                .with_body(body)
+                .create();
+
+            match update_repo_name("owner", "test-repo", "new-name") {
+                Err(e) => assert!(
+                    e.to_string().contains(expected),
+                    "Expected '{}', got: {}",
+                    expected,
+                    e
+                ),
+                Ok(_) => panic!("Expected permission error"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limited() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        // Secondary rate limit on a write
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _mock = server
+            .mock("PATCH", "/repos/owner/test-repo")
+            .with_status(403)
+            .with_header("retry-after", "60")
+            .with_body(r#"{"message":"You have exceeded a secondary rate limit. Please wait a few minutes before you try again."}"#)
+            .create();
+
+        let err = update_repo_name("owner", "test-repo", "new-name").unwrap_err();
+        assert!(
+            matches!(err, Error::RateLimited { retry_after: Some(delay) } if delay == Duration::from_secs(60)),
+            "Expected rate limit error, got: {}",
+            err
+        );
+        assert!(err.to_string().contains("Retry in 60 seconds"), "{}", err);
+
+        // Primary rate limit on a lookup
+        let reset = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs()
+            + 120;
+        let _mock = server
+            .mock("GET", "/repos/owner/test-repo")
+            .with_status(403)
+            .with_header("x-ratelimit-remaining", "0")
+            .with_header("x-ratelimit-reset", &reset.to_string())
+            .with_body(r#"{"message":"API rate limit exceeded for user ID 1."}"#)
+            .create();
+
+        let err = get_repo_info("owner", "test-repo").unwrap_err();
+        match err {
+            Error::RateLimited {
+                retry_after: Some(delay),
+            } => assert!((115..=120).contains(&delay.as_secs()), "{:?}", delay),
+            _ => panic!("Expected rate limit error, got: {}", err),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_repo_name_graphql() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+        use mockito::Matcher;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let lookup = server
+            .mock("POST", "/graphql")
+            .match_body(Matcher::PartialJson(
+                json!({ "variables": { "owner": "owner", "name": "old-name" } }),
+            ))
+            .with_status(200)
+            .with_body(r#"{"data":{"repository":{"id":"R_123"}}}"#)
+            .create();
+        let mutation = server
+            .mock("POST", "/graphql")
+            .match_body(Matcher::PartialJson(
+                json!({ "variables": { "id": "R_123", "name": "new-name" } }),
+            ))
+            .with_status(200)
+            .with_body(
+                r#"{"data":{"updateRepository":{"repository":{"name":"new-name","nameWithOwner":"owner/new-name","url":"https://github.com/owner/new-name"}}}}"#,
+            )
+            .create();
+
+        let repo_info = update_repo_name_graphql("owner", "old-name", "new-name")?;
+
+        lookup.assert();
+        mutation.assert();
+        assert_eq!(repo_info.name, "new-name");
+        assert_eq!(repo_info.full_name, "owner/new-name");
+        assert_eq!(repo_info.clone_url, "https://github.com/owner/new-name.git");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_repos_url() {
+        let cases = [
+            (
+                "https://api.github.com",
+                "https://api.github.com/repos/owner/repo",
+            ),
+            (
+                "https://api.github.com/",
+                "https://api.github.com/repos/owner/repo",
+            ),
+            (
+                "https://github.example.com/api/v3",
+                "https://github.example.com/api/v3/repos/owner/repo",
+            ),
+            (
+                "https://github.example.com/api/v3/",
+                "https://github.example.com/api/v3/repos/owner/repo",
+            ),
+        ];
+
+        for (base, expected) in cases {
+            let url = join_repos_url(base, "owner", "repo");
+            assert_eq!(url, expected);
+            assert!(!url["https://".len()..].contains("//"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_get_base_url_from_config() -> anyhow::Result<()> {
+        use crate::test_helpers;
+        use assert_fs::TempDir;
+
+        let temp = TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let original = std::env::var_os("GITHUB_API_BASE_URL");
+        std::env::remove_var("GITHUB_API_BASE_URL");
+
+        let config = Config::load_from(temp.path().join("config"))?;
+        config.set_api_base_url("https://github.example.com/api/v3/")?;
+        let configured = config::with_config(&config, get_base_url);
+        config.set_api_base_url("")?;
+        let default = config::with_config(&config, get_base_url);
+
+        if let Some(original) = original {
+            std::env::set_var("GITHUB_API_BASE_URL", original);
+        }
+
+        assert_eq!(configured, "https://github.example.com/api/v3");
+        assert_eq!(default, "https://api.github.com");
+
+        Ok(())
+    }
 }
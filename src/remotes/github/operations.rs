@@ -1,71 +1,141 @@
 use crate::{
-    git,
-    remotes::github::{
-        client::get_repo_info, client::update_repo_name, url::format_new_remote_url,
-        url::parse_github_url,
+    config::{self, GitHubApiBackend},
+    progress::TextSink,
+    remotes::{
+        self,
+        github::{
+            client::find_repo_info, client::get_repo_info, client::graphql_url,
+            client::replace_repo_topics, client::repos_url, client::transfer_repo,
+            client::update_repo_default_branch, client::update_repo_description,
+            client::update_repo_name, client::update_repo_name_graphql, client::verify_token,
+            client::GitHubRepo, provider::GitHubProvider, url::format_new_remote_url,
+            url::parse_github_url, url::validate_github_repo_name,
+        },
     },
-    types::{Error, Result},
-    utils::fs,
+    types::{DenialReason, Error, PullOptions, PushOptions, Result},
+    utils::prompt,
 };
 use git2::Repository;
+use log::{debug, log_enabled, Level};
+use std::io::BufRead;
+use std::time::Duration;
+
+/// How often, and how many times, to look for a transferred repository at its new
+/// location before giving up.
+const TRANSFER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const TRANSFER_POLL_ATTEMPTS: u32 = 5;
+
+/// Returns whether the directory name or remote URL was (or, in dry-run mode,
+/// would be) changed.
+pub fn pull_from_github_remote(
+    repo: &Repository,
+    remote_url: &str,
+    options: &PullOptions,
+) -> Result<bool> {
+    let provider = GitHubProvider {
+        name_source: options.name_source,
+    };
+    remotes::pull(&provider, repo, remote_url, options, &TextSink)
+}
 
-pub fn pull_from_github_remote(repo: &Repository, remote_url: &str, dry_run: bool) -> Result<()> {
-    let (owner, remote_repo_name) = parse_github_url(remote_url)?;
-
-    let local_directory_name = git::get_local_directory_name(repo)?;
-    let repo_info = get_repo_info(&owner, &remote_repo_name)?;
-    let resolved_repo_name = repo_info.name;
-    let resolved_owner = repo_info.full_name.split('/').next().unwrap_or(&owner);
-
-    let repo_path = repo
-        .workdir()
-        .ok_or_else(|| Error::Fs("Cannot get repository working directory".into()))?;
-
-    let resolved_remote_url =
-        format_new_remote_url(remote_url, resolved_owner, &resolved_repo_name);
-    let should_rename_directory = local_directory_name != resolved_repo_name;
-    let should_change_remote = resolved_remote_url != remote_url;
+pub fn push_to_github_remote(
+    repo: &Repository,
+    remote_url: &str,
+    options: &PushOptions,
+) -> Result<()> {
+    if options.verify_token {
+        verify_token()?;
+    }
 
-    if !should_rename_directory && !should_change_remote {
-        println!("Directory name and remote URL already up-to-date");
-        return Ok(());
+    let target_name = remotes::push_target_name(repo, options)?;
+    let (_, owner, remote_repo_name) = parse_github_url(remote_url)?;
+    debug!(
+        "Pushing '{}' to {}/{}: should_rename_remote={}",
+        target_name,
+        owner,
+        remote_repo_name,
+        remote_repo_name != target_name
+    );
+
+    if remote_repo_name != target_name {
+        validate_github_repo_name(&target_name)?;
     }
 
-    if should_change_remote {
-        git::set_remote_url(repo, remote_url, &resolved_remote_url, dry_run)?;
+    if let Some(new_owner) = options
+        .owner
+        .as_deref()
+        .filter(|new_owner| !new_owner.eq_ignore_ascii_case(&owner))
+    {
+        return transfer_to_owner(
+            repo,
+            remote_url,
+            (&owner, &remote_repo_name),
+            (new_owner, &target_name),
+            options,
+        );
     }
 
-    if should_rename_directory {
-        fs::rename_directory(repo_path, &resolved_repo_name, dry_run)?;
+    if remote_repo_name == target_name {
+        println!("Repository name already matches the local directory name");
+        return sync_repo_metadata(&owner, &remote_repo_name, options);
     }
 
-    Ok(())
-}
+    // GitHub rejects renaming an archived repository with a 403 that reads like a
+    // token problem
+    if get_repo_info(&owner, &remote_repo_name)?.archived {
+        return Err(Error::GitHubApi(
+            "Repository is archived; unarchive it before renaming.".to_string(),
+        ));
+    }
 
-pub fn push_to_github_remote(repo: &Repository, remote_url: &str, dry_run: bool) -> Result<()> {
-    let local_directory_name = git::get_local_directory_name(repo)?;
-    let (owner, remote_repo_name) = parse_github_url(remote_url)?;
+    check_name_available(&owner, &remote_repo_name, &target_name)?;
 
-    if remote_repo_name == local_directory_name {
-        println!("Repository name already matches the local directory name");
-        return Ok(());
+    if options.safety_check {
+        check_rename_safety(
+            &owner,
+            &remote_repo_name,
+            options.dry_run,
+            &mut std::io::stdin().lock(),
+        )?;
     }
 
-    if dry_run {
+    if options.dry_run {
         println!(
             "Would update GitHub repository name from '{}' to '{}'",
-            remote_repo_name, local_directory_name
+            remote_repo_name, target_name
         );
-        let would_change_url = format_new_remote_url(remote_url, &owner, &local_directory_name);
-        println!(
-            "Would change 'origin' remote from '{}' to '{}'",
-            remote_url, would_change_url
-        );
-        return Ok(());
+        if log_enabled!(Level::Debug) {
+            println!(
+                "{}",
+                describe_rename_request(&owner, &remote_repo_name, &target_name)
+            );
+        }
+        let would_change_url = format_new_remote_url(remote_url, &owner, &target_name);
+        remotes::set_pushed_remote_url(repo, remote_url, &would_change_url, true)?;
+        // The rename has not happened, so the metadata lives under the old name
+        return sync_repo_metadata(&owner, &remote_repo_name, options);
     }
 
-    let updated_repo = match update_repo_name(&owner, &remote_repo_name, &local_directory_name) {
+    let rename = match config::current().get_github_api() {
+        GitHubApiBackend::Rest => update_repo_name,
+        GitHubApiBackend::Graphql => update_repo_name_graphql,
+    };
+    let updated_repo = match rename(&owner, &remote_repo_name, &target_name) {
         Ok(repo_info) => repo_info,
+        // Organizations can restrict renaming to admins, which a token with the
+        // right permission can still run into
+        Err(Error::PermissionDenied {
+            reason: DenialReason::MissingPermission,
+            message,
+        }) if get_repo_info(&owner, &remote_repo_name).is_ok_and(|r| r.is_org_owned()) => {
+            return Err(Error::PermissionDenied {
+                reason: DenialReason::MissingPermission,
+                message: format!(
+                    "{} The organization '{}' may restrict renaming repositories to admins.",
+                    message, owner
+                ),
+            });
+        }
         Err(e) => {
             return Err(e);
         }
@@ -74,7 +144,222 @@ pub fn push_to_github_remote(repo: &Repository, remote_url: &str, dry_run: bool)
     let resolved_owner = updated_repo.full_name.split('/').next().unwrap_or(&owner);
 
     let new_remote_url = format_new_remote_url(remote_url, resolved_owner, &updated_repo.name);
-    git::set_remote_url(repo, remote_url, &new_remote_url, false)?;
+    remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, false)?;
+
+    sync_repo_metadata(resolved_owner, &updated_repo.name, options)
+}
+
+/// The API request `push` sends to rename the repository, for verbose dry runs.
+fn describe_rename_request(owner: &str, repo_name: &str, new_name: &str) -> String {
+    match config::current().get_github_api() {
+        GitHubApiBackend::Rest => format!(
+            "Would PATCH {} with name={}",
+            repos_url(owner, repo_name),
+            new_name
+        ),
+        GitHubApiBackend::Graphql => format!(
+            "Would POST {} with updateRepository name={}",
+            graphql_url(),
+            new_name
+        ),
+    }
+}
+
+/// Moves the repository to `new_owner` (renaming it on the way if needed) and
+/// points the remote at the new location once GitHub has finished the transfer.
+fn transfer_to_owner(
+    repo: &Repository,
+    remote_url: &str,
+    (owner, repo_name): (&str, &str),
+    (new_owner, new_name): (&str, &str),
+    options: &PushOptions,
+) -> Result<()> {
+    if options.safety_check {
+        check_rename_safety(
+            owner,
+            repo_name,
+            options.dry_run,
+            &mut std::io::stdin().lock(),
+        )?;
+    }
+
+    let new_remote_url = format_new_remote_url(remote_url, new_owner, new_name);
+
+    if options.dry_run {
+        println!(
+            "Would transfer GitHub repository '{}/{}' to '{}/{}'",
+            owner, repo_name, new_owner, new_name
+        );
+        remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, true)?;
+        // The transfer has not happened, so the metadata lives under the old location
+        return sync_repo_metadata(owner, repo_name, options);
+    }
+
+    println!(
+        "Transferring GitHub repository '{}/{}' to '{}/{}'",
+        owner, repo_name, new_owner, new_name
+    );
+    transfer_repo(owner, repo_name, new_owner, new_name)?;
+    let transferred = wait_for_transfer(new_owner, new_name)?;
+
+    let new_remote_url = format_new_remote_url(remote_url, new_owner, &transferred.name);
+    remotes::set_pushed_remote_url(repo, remote_url, &new_remote_url, false)?;
+
+    sync_repo_metadata(new_owner, &transferred.name, options)
+}
+
+/// Polls the new location until the transferred repository shows up there.
+fn wait_for_transfer(new_owner: &str, new_name: &str) -> Result<GitHubRepo> {
+    for attempt in 1..=TRANSFER_POLL_ATTEMPTS {
+        if let Some(repo_info) = find_repo_info(new_owner, new_name)? {
+            return Ok(repo_info);
+        }
+        debug!(
+            "{}/{} not available yet (attempt {}/{})",
+            new_owner, new_name, attempt, TRANSFER_POLL_ATTEMPTS
+        );
+        if attempt < TRANSFER_POLL_ATTEMPTS {
+            std::thread::sleep(TRANSFER_POLL_INTERVAL);
+        }
+    }
+
+    Err(Error::GitHubApi(format!(
+        "The transfer to '{}/{}' was accepted but is not complete yet. Run 'git-repo-name pull' once it finishes to update the remote URL.",
+        new_owner, new_name
+    )))
+}
+
+/// Fails if the owner already has another repository with the target name, so the
+/// rename is rejected before anything is mutated.
+fn check_name_available(owner: &str, remote_repo_name: &str, new_name: &str) -> Result<()> {
+    let Some(existing) = find_repo_info(owner, new_name)? else {
+        return Ok(());
+    };
+
+    // GitHub redirects old names of renamed repositories, so the lookup may
+    // resolve to the very repository being renamed
+    let current_full_name = format!("{}/{}", owner, remote_repo_name);
+    if existing.full_name.eq_ignore_ascii_case(&current_full_name) {
+        return Ok(());
+    }
+
+    Err(Error::GitHubApi(format!(
+        "You already have a repo named '{}' at {}",
+        new_name, existing.full_name
+    )))
+}
+
+/// Warns about things that break when a repository is renamed (GitHub Pages URLs,
+/// forks relying on redirects) and asks for confirmation before proceeding.
+fn check_rename_safety(
+    owner: &str,
+    repo_name: &str,
+    dry_run: bool,
+    input: &mut dyn BufRead,
+) -> Result<()> {
+    let repo_info = get_repo_info(owner, repo_name)?;
+    let mut warnings = Vec::new();
+
+    if repo_info.has_pages {
+        warnings.push(format!(
+            "GitHub Pages is enabled for '{}'; its published URL will change after the rename",
+            repo_info.full_name
+        ));
+    }
+    if repo_info.forks_count > 0 {
+        warnings.push(format!(
+            "'{}' has {} fork(s); their upstream links will rely on GitHub's redirect",
+            repo_info.full_name, repo_info.forks_count
+        ));
+    }
+
+    if warnings.is_empty() {
+        return Ok(());
+    }
+
+    for warning in &warnings {
+        println!("Warning: {}", warning);
+    }
+
+    if dry_run || prompt::confirm(input, "Rename the repository anyway?")? {
+        Ok(())
+    } else {
+        Err(Error::Config("Push aborted by safety check".into()))
+    }
+}
+
+/// Updates the repository description and topics when requested and different
+/// from the current values on GitHub.
+fn sync_repo_metadata(owner: &str, repo_name: &str, options: &PushOptions) -> Result<()> {
+    if options.description.is_none()
+        && options.topics.is_empty()
+        && options.default_branch.is_none()
+    {
+        return Ok(());
+    }
+
+    let repo_info = get_repo_info(owner, repo_name)?;
+
+    if let Some(description) = &options.description {
+        let current_description = repo_info.description.as_deref().unwrap_or("");
+        if current_description == description {
+            println!("Repository description already up-to-date");
+        } else if options.dry_run {
+            println!(
+                "Would update GitHub repository description from '{}' to '{}'",
+                current_description, description
+            );
+        } else {
+            println!(
+                "Updating GitHub repository description from '{}' to '{}'",
+                current_description, description
+            );
+            update_repo_description(owner, repo_name, description)?;
+        }
+    }
+
+    if !options.topics.is_empty() {
+        let mut current_topics = repo_info.topics.clone();
+        current_topics.sort();
+        let mut new_topics = options.topics.clone();
+        new_topics.sort();
+        new_topics.dedup();
+
+        if current_topics == new_topics {
+            println!("Repository topics already up-to-date");
+        } else if options.dry_run {
+            println!(
+                "Would update GitHub repository topics from [{}] to [{}]",
+                current_topics.join(", "),
+                new_topics.join(", ")
+            );
+        } else {
+            println!(
+                "Updating GitHub repository topics from [{}] to [{}]",
+                current_topics.join(", "),
+                new_topics.join(", ")
+            );
+            replace_repo_topics(owner, repo_name, &new_topics)?;
+        }
+    }
+
+    if let Some(default_branch) = &options.default_branch {
+        let current_branch = repo_info.default_branch.as_deref().unwrap_or("");
+        if current_branch == default_branch {
+            println!("Repository default branch already up-to-date");
+        } else if options.dry_run {
+            println!(
+                "Would change GitHub repository default branch from '{}' to '{}'",
+                current_branch, default_branch
+            );
+        } else {
+            println!(
+                "Changing GitHub repository default branch from '{}' to '{}'",
+                current_branch, default_branch
+            );
+            update_repo_default_branch(owner, repo_name, default_branch)?;
+        }
+    }
 
     Ok(())
 }
@@ -82,7 +367,7 @@ pub fn push_to_github_remote(repo: &Repository, remote_url: &str, dry_run: bool)
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_helpers;
+    use crate::{config::PullNameSource, git, test_helpers};
 
     struct PullTestSetup {
         temp: assert_fs::TempDir,
@@ -107,6 +392,13 @@ mod tests {
         })
     }
 
+    fn dry_run_options() -> PullOptions {
+        PullOptions {
+            dry_run: true,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_pull_up_to_date_dry_run() -> anyhow::Result<()> {
         let pull_test_setup = setup_for_pull_test("test-repo")?;
@@ -115,7 +407,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, remote_url, true)
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &dry_run_options())
         })?;
 
         assert!(
@@ -138,7 +430,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, remote_url, false)
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &PullOptions::default())
         })?;
 
         assert!(
@@ -153,6 +445,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pull_owner_differs_only_in_case() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("repo")?;
+        let remote_url = "git@github.com:MyOrg/repo.git";
+        test_helpers::mock_github_get_repo("MyOrg", "myorg", "repo", "repo");
+        pull_test_setup.repo.remote("origin", remote_url)?;
+
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &PullOptions::default())
+        })?;
+
+        assert!(!changed);
+        assert!(
+            output.contains("Directory name and remote URL already up-to-date")
+                && !output.contains("has moved"),
+            "Expected up-to-date message, got: {}",
+            output
+        );
+        assert_eq!(remote_url, git::get_remote_url(&pull_test_setup.repo)?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_pull_remote_url_update_dry_run() -> anyhow::Result<()> {
         let pull_test_setup = setup_for_pull_test("repo-name")?;
@@ -162,7 +477,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", old_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, old_url, true)
+            pull_from_github_remote(&pull_test_setup.repo, old_url, &dry_run_options())
         })?;
 
         assert!(
@@ -189,7 +504,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", old_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, old_url, false)
+            pull_from_github_remote(&pull_test_setup.repo, old_url, &PullOptions::default())
         })?;
 
         assert!(
@@ -219,7 +534,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, remote_url, true)
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &dry_run_options())
         })?;
 
         assert!(
@@ -248,7 +563,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, remote_url, false)
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &PullOptions::default())
         })?;
 
         assert!(
@@ -279,7 +594,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", old_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, old_url, true)
+            pull_from_github_remote(&pull_test_setup.repo, old_url, &dry_run_options())
         })?;
 
         assert!(
@@ -318,7 +633,7 @@ mod tests {
         pull_test_setup.repo.remote("origin", old_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            pull_from_github_remote(&pull_test_setup.repo, old_url, false)
+            pull_from_github_remote(&pull_test_setup.repo, old_url, &PullOptions::default())
         })?;
 
         assert!(
@@ -349,12 +664,104 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pull_refuses_dirty_worktree() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("old-name")?;
+        let remote_url = "git@github.com:owner/new-name.git";
+        pull_test_setup.repo.remote("origin", remote_url)?;
+        test_helpers::mock_github_get_repo("owner", "owner", "new-name", "new-name");
+        std::fs::write(
+            pull_test_setup
+                .temp
+                .path()
+                .join("old-name")
+                .join("notes.txt"),
+            "work in progress",
+        )?;
+
+        let result =
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &PullOptions::default());
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Error: Working tree has uncommitted changes; pass --force to rename anyway"
+            ),
+            Ok(_) => panic!("Expected the rename to be blocked"),
+        }
+        test_helpers::assert_directory_existence(&pull_test_setup.temp, "old-name", true)?;
+
+        let options = PullOptions {
+            force: true,
+            ..Default::default()
+        };
+        test_helpers::capture_stdout(|| {
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &options)
+        })?;
+        test_helpers::assert_directory_existence(&pull_test_setup.temp, "old-name", false)?;
+        test_helpers::assert_directory_existence(&pull_test_setup.temp, "new-name", true)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_follows_redirect() -> anyhow::Result<()> {
+        let pull_test_setup = setup_for_pull_test("new-name")?;
+        let old_url = "https://github.com/old-owner/old-name.git";
+        let expected_new_url = "https://github.com/new-owner/new-name.git";
+
+        test_helpers::mock_github_get_repo("old-owner", "new-owner", "old-name", "new-name");
+        pull_test_setup.repo.remote("origin", old_url)?;
+
+        let (output, _) = test_helpers::capture_stdout(|| {
+            pull_from_github_remote(&pull_test_setup.repo, old_url, &PullOptions::default())
+        })?;
+
+        assert!(
+            output.contains("Repository 'old-owner/old-name' has moved to 'new-owner/new-name'"),
+            "Expected redirect message, got: {}",
+            output
+        );
+        assert_eq!(
+            expected_new_url,
+            git::get_remote_url(&pull_test_setup.repo)?
+        );
+        test_helpers::assert_directory_existence(&pull_test_setup.temp, "new-name", true)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_name_source() -> anyhow::Result<()> {
+        for (name_source, expected_dir) in [
+            (PullNameSource::Api, "My-Repo"),
+            (PullNameSource::Url, "my-repo"),
+        ] {
+            let pull_test_setup = setup_for_pull_test("local-name")?;
+            let remote_url = "https://github.com/owner/my-repo.git";
+            test_helpers::mock_github_get_repo("owner", "owner", "my-repo", "My-Repo");
+            pull_test_setup.repo.remote("origin", remote_url)?;
+
+            let options = PullOptions {
+                name_source: Some(name_source),
+                ..Default::default()
+            };
+            test_helpers::capture_stdout(|| {
+                pull_from_github_remote(&pull_test_setup.repo, remote_url, &options)
+            })?;
+
+            test_helpers::assert_directory_existence(&pull_test_setup.temp, expected_dir, true)?;
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_pull_invalid_github_url() -> anyhow::Result<()> {
         let pull_test_setup = setup_for_pull_test("test-repo")?;
         let invalid_url = "https://not-github.com/owner/repo.git";
 
-        let result = pull_from_github_remote(&pull_test_setup.repo, invalid_url, false);
+        let result =
+            pull_from_github_remote(&pull_test_setup.repo, invalid_url, &PullOptions::default());
 
         match result {
             Err(e) => {
@@ -383,7 +790,8 @@ mod tests {
             test_helpers::mock_github_get_repo("owner", "owner", "test-repo", "test-repo");
             pull_test_setup.repo.remote("origin", url)?;
 
-            let result = pull_from_github_remote(&pull_test_setup.repo, url, false);
+            let result =
+                pull_from_github_remote(&pull_test_setup.repo, url, &PullOptions::default());
             assert!(result.is_ok(), "Failed with URL format: {}", url);
             pull_test_setup.repo.remote_delete("origin")?;
         }
@@ -399,7 +807,8 @@ mod tests {
         test_helpers::mock_github_get_repo_error("owner", "test-repo");
         pull_test_setup.repo.remote("origin", remote_url)?;
 
-        let result = pull_from_github_remote(&pull_test_setup.repo, remote_url, false);
+        let result =
+            pull_from_github_remote(&pull_test_setup.repo, remote_url, &PullOptions::default());
 
         match result {
             Err(e) => {
@@ -442,7 +851,7 @@ mod tests {
         push_test_setup.repo.remote("origin", remote_url)?;
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            push_to_github_remote(&push_test_setup.repo, remote_url, false)
+            push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default())
         })?;
 
         assert!(
@@ -461,9 +870,23 @@ mod tests {
         let remote_url = "https://github.com/owner/old-name.git";
 
         push_test_setup.repo.remote("origin", remote_url)?;
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        test_helpers::mock_github_existing_repo(&mut server, "owner", "old-name");
+        let _free_name = server
+            .mock("GET", "/repos/owner/new-name")
+            .with_status(404)
+            .create();
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            push_to_github_remote(&push_test_setup.repo, remote_url, true)
+            push_to_github_remote(
+                &push_test_setup.repo,
+                remote_url,
+                &PushOptions {
+                    dry_run: true,
+                    ..Default::default()
+                },
+            )
         })?;
 
         assert!(
@@ -476,6 +899,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_push_dry_run_reports_selected_remote() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let own_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("upstream", own_url)?;
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        test_helpers::mock_github_existing_repo(&mut server, "owner", "old-name");
+        test_helpers::mock_github_existing_repo(&mut server, "owner", "other");
+        let _free_name = server
+            .mock("GET", "/repos/owner/new-name")
+            .with_status(404)
+            .create();
+        let dry_run = PushOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+
+        let (own, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, own_url, &dry_run)
+        })?;
+        // A --remote-url naming another repository leaves the remote alone
+        let (foreign, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(
+                &push_test_setup.repo,
+                "https://github.com/owner/other.git",
+                &dry_run,
+            )
+        })?;
+
+        assert!(
+            own.contains(
+                "Would change 'upstream' remote from 'https://github.com/owner/old-name.git' \
+                 to 'https://github.com/owner/new-name.git'"
+            ),
+            "{}",
+            own
+        );
+        assert!(
+            foreign.contains("Would update GitHub repository name from 'other' to 'new-name'"),
+            "{}",
+            foreign
+        );
+        assert!(!foreign.contains("remote from"), "{}", foreign);
+        assert_eq!(own_url, git::get_remote_url(&push_test_setup.repo)?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_push_update_repo_name() -> anyhow::Result<()> {
         let push_test_setup = setup_for_push_test("new-name")?;
@@ -486,7 +958,7 @@ mod tests {
         test_helpers::mock_github_update_repo("owner", "owner", "old-name", "new-name");
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            push_to_github_remote(&push_test_setup.repo, old_url, false)
+            push_to_github_remote(&push_test_setup.repo, old_url, &PushOptions::default())
         })?;
 
         assert!(
@@ -511,10 +983,10 @@ mod tests {
         let remote_url = "https://github.com/owner/old-name.git";
 
         push_test_setup.repo.remote("origin", remote_url)?;
-        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "old-name");
         test_helpers::mock_github_update_repo_error("owner", "old-name", 403);
 
-        let result = push_to_github_remote(&push_test_setup.repo, remote_url, false);
+        let result =
+            push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default());
 
         match result {
             Err(e) => {
@@ -531,6 +1003,244 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_push_org_permission_denied() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let remote_url = "https://github.com/acme/old-name.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _free_name = server
+            .mock("GET", "/repos/acme/new-name")
+            .with_status(404)
+            .create();
+        let _repo = server
+            .mock("GET", "/repos/acme/old-name")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "name": "old-name",
+                    "full_name": "acme/old-name",
+                    "clone_url": "https://github.com/acme/old-name.git",
+                    "owner": { "login": "acme", "type": "Organization" }
+                })
+                .to_string(),
+            )
+            .create();
+        let _patch = server
+            .mock("PATCH", "/repos/acme/old-name")
+            .with_status(403)
+            .with_body(r#"{"message":"Must have admin rights to Repository."}"#)
+            .create();
+
+        let err = push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default())
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("The organization 'acme' may restrict renaming repositories to admins."),
+            "Expected organization hint, got: {}",
+            err
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_archived_repo() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let remote_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _repo = server
+            .mock("GET", "/repos/owner/old-name")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "name": "old-name",
+                    "full_name": "owner/old-name",
+                    "clone_url": "https://github.com/owner/old-name.git",
+                    "archived": true
+                })
+                .to_string(),
+            )
+            .create();
+        let patch = server
+            .mock("PATCH", mockito::Matcher::Any)
+            .expect(0)
+            .create();
+
+        let err = push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "GitHub API error: Repository is archived; unarchive it before renaming."
+        );
+        patch.assert();
+        assert_eq!(remote_url, git::get_remote_url(&push_test_setup.repo)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_verify_token_aborts_early() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let remote_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        std::env::set_var("GITHUB_TOKEN", "expired-token");
+        let _user = server.mock("GET", "/user").with_status(401).create();
+        let lookups = server
+            .mock("GET", mockito::Matcher::Regex("^/repos/".into()))
+            .expect(0)
+            .create();
+        let patch = server
+            .mock("PATCH", mockito::Matcher::Any)
+            .expect(0)
+            .create();
+
+        let result = push_to_github_remote(
+            &push_test_setup.repo,
+            remote_url,
+            &PushOptions {
+                verify_token: true,
+                ..Default::default()
+            },
+        );
+        std::env::remove_var("GITHUB_TOKEN");
+
+        let err = result.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("GitHub token is invalid or expired"),
+            "Expected token error, got: {}",
+            err
+        );
+        lookups.assert();
+        patch.assert();
+        assert_eq!(remote_url, git::get_remote_url(&push_test_setup.repo)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_rejects_existing_repo_name() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("taken-name")?;
+        let remote_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        test_helpers::mock_github_existing_repo(&mut server, "owner", "old-name");
+        let _get_mock = server
+            .mock("GET", "/repos/owner/taken-name")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "taken-name",
+                    "full_name": "owner/taken-name",
+                    "clone_url": "https://github.com/owner/taken-name.git"
+                })
+                .to_string(),
+            )
+            .create();
+        let patch_mock = server
+            .mock("PATCH", "/repos/owner/old-name")
+            .expect(0)
+            .create();
+
+        let result =
+            push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default());
+
+        patch_mock.assert();
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "GitHub API error: You already have a repo named 'taken-name' at owner/taken-name"
+            ),
+            Ok(_) => panic!("Expected error, but operation succeeded"),
+        }
+        assert_eq!(remote_url, git::get_remote_url(&push_test_setup.repo)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_explicit_name() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("my-repo-wip")?;
+        let old_url = "git@github.com:owner/old-name.git";
+        push_test_setup.repo.remote("origin", old_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        test_helpers::mock_github_existing_repo(&mut server, "owner", "old-name");
+        let _free_name = server
+            .mock("GET", "/repos/owner/my-repo")
+            .with_status(404)
+            .create();
+        let patch = server
+            .mock("PATCH", "/repos/owner/old-name")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "name": "my-repo" }),
+            ))
+            .with_body(
+                serde_json::json!({
+                    "name": "my-repo",
+                    "full_name": "owner/my-repo",
+                    "clone_url": "https://github.com/owner/my-repo.git"
+                })
+                .to_string(),
+            )
+            .create();
+
+        test_helpers::capture_stdout(|| {
+            push_to_github_remote(
+                &push_test_setup.repo,
+                old_url,
+                &PushOptions {
+                    name: Some("my-repo".to_string()),
+                    ..Default::default()
+                },
+            )
+        })?;
+
+        patch.assert();
+        assert_eq!(
+            "git@github.com:owner/my-repo.git",
+            git::get_remote_url(&push_test_setup.repo)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_proceeds_when_name_is_free() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("free-name")?;
+        let old_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("origin", old_url)?;
+
+        let (get_mock, patch_mock) =
+            test_helpers::mock_github_update_repo("owner", "owner", "old-name", "free-name");
+
+        test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, old_url, &PushOptions::default())
+        })?;
+
+        get_mock.assert();
+        patch_mock.assert();
+        assert_eq!(
+            "https://github.com/owner/free-name.git",
+            git::get_remote_url(&push_test_setup.repo)?
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_push_owner_change() -> anyhow::Result<()> {
         let push_test_setup = setup_for_push_test("new-name")?;
@@ -541,7 +1251,7 @@ mod tests {
         test_helpers::mock_github_update_repo("old-owner", "new-owner", "old-name", "new-name");
 
         let (output, _) = test_helpers::capture_stdout(|| {
-            push_to_github_remote(&push_test_setup.repo, old_url, false)
+            push_to_github_remote(&push_test_setup.repo, old_url, &PushOptions::default())
         })?;
 
         assert!(
@@ -559,4 +1269,310 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_push_rejects_invalid_repo_name() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("my repo")?;
+        let remote_url = "https://github.com/owner/my-repo.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        // No API mocks: the name must be rejected before any request is made
+        let result =
+            push_to_github_remote(&push_test_setup.repo, remote_url, &PushOptions::default());
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Error: Directory name 'my repo' contains a space, which GitHub repo names can't have"
+            ),
+            Ok(_) => panic!("Expected error for invalid repository name"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_transfers_to_new_owner() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let old_url = "git@github.com:old-owner/old-name.git";
+        push_test_setup.repo.remote("origin", old_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let repo_body = serde_json::json!({
+            "name": "new-name",
+            "full_name": "new-org/new-name",
+            "clone_url": "https://github.com/new-org/new-name.git"
+        })
+        .to_string();
+
+        let transfer_mock = server
+            .mock("POST", "/repos/old-owner/old-name/transfer")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "new_owner": "new-org",
+                "new_name": "new-name"
+            })))
+            .with_status(202)
+            .with_body(&repo_body)
+            .create();
+        // The transfer completes in the background: not found at first, then there
+        let pending_mock = server
+            .mock("GET", "/repos/new-org/new-name")
+            .with_status(404)
+            .expect(1)
+            .create();
+        let done_mock = server
+            .mock("GET", "/repos/new-org/new-name")
+            .with_status(200)
+            .with_body(&repo_body)
+            .create();
+
+        let options = PushOptions {
+            owner: Some("new-org".to_string()),
+            ..Default::default()
+        };
+        let (output, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, old_url, &options)
+        })?;
+
+        transfer_mock.assert();
+        pending_mock.assert();
+        done_mock.assert();
+        assert!(
+            output.contains(
+                "Transferring GitHub repository 'old-owner/old-name' to 'new-org/new-name'"
+            ),
+            "Expected transfer message, got: {}",
+            output
+        );
+        assert_eq!(
+            "git@github.com:new-org/new-name.git",
+            git::get_remote_url(&push_test_setup.repo)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_updates_description_and_topics() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("test-repo")?;
+        let remote_url = "https://github.com/owner/test-repo.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let repo_body = serde_json::json!({
+            "name": "test-repo",
+            "full_name": "owner/test-repo",
+            "clone_url": "https://github.com/owner/test-repo.git",
+            "description": "Old description",
+            "topics": ["rust"]
+        });
+        let _get_mock = server
+            .mock("GET", "/repos/owner/test-repo")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(repo_body.to_string())
+            .create();
+        let patch_mock = server
+            .mock("PATCH", "/repos/owner/test-repo")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "description": "New description" }),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(repo_body.to_string())
+            .create();
+        let topics_mock = server
+            .mock("PUT", "/repos/owner/test-repo/topics")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "names": ["cli", "rust"] }),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"names": ["cli", "rust"]}"#)
+            .create();
+
+        let options = PushOptions {
+            description: Some("New description".to_string()),
+            topics: vec!["rust".to_string(), "cli".to_string()],
+            ..Default::default()
+        };
+        let (output, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, remote_url, &options)
+        })?;
+
+        patch_mock.assert();
+        topics_mock.assert();
+        assert!(
+            output.contains(
+                "Updating GitHub repository description from 'Old description' to 'New description'"
+            ),
+            "Expected description update message, got: {}",
+            output
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_skips_unchanged_description() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("test-repo")?;
+        let remote_url = "https://github.com/owner/test-repo.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _get_mock = server
+            .mock("GET", "/repos/owner/test-repo")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "test-repo",
+                    "full_name": "owner/test-repo",
+                    "clone_url": "https://github.com/owner/test-repo.git",
+                    "description": "Same description"
+                })
+                .to_string(),
+            )
+            .create();
+        let patch_mock = server
+            .mock("PATCH", "/repos/owner/test-repo")
+            .expect(0)
+            .create();
+
+        let options = PushOptions {
+            description: Some("Same description".to_string()),
+            ..Default::default()
+        };
+        let (output, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, remote_url, &options)
+        })?;
+
+        patch_mock.assert();
+        assert!(
+            output.contains("Repository description already up-to-date"),
+            "Expected up-to-date description message, got: {}",
+            output
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_updates_default_branch() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("test-repo")?;
+        let remote_url = "https://github.com/owner/test-repo.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _get_mock = server
+            .mock("GET", "/repos/owner/test-repo")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "test-repo",
+                    "full_name": "owner/test-repo",
+                    "clone_url": "https://github.com/owner/test-repo.git",
+                    "default_branch": "master"
+                })
+                .to_string(),
+            )
+            .create();
+        let patch_mock = server
+            .mock("PATCH", "/repos/owner/test-repo")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "default_branch": "main" }),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "test-repo",
+                    "full_name": "owner/test-repo",
+                    "clone_url": "https://github.com/owner/test-repo.git",
+                    "default_branch": "main"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let options = PushOptions {
+            default_branch: Some("main".to_string()),
+            ..Default::default()
+        };
+        let (output, _) = test_helpers::capture_stdout(|| {
+            push_to_github_remote(&push_test_setup.repo, remote_url, &options)
+        })?;
+
+        patch_mock.assert();
+        assert!(
+            output.contains("Changing GitHub repository default branch from 'master' to 'main'"),
+            "Expected default branch message, got: {}",
+            output
+        );
+        assert_eq!(
+            update_repo_default_branch("owner", "test-repo", "main")?
+                .default_branch
+                .as_deref(),
+            Some("main")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_safety_check_with_pages_enabled() -> anyhow::Result<()> {
+        let push_test_setup = setup_for_push_test("new-name")?;
+        let remote_url = "https://github.com/owner/old-name.git";
+        push_test_setup.repo.remote("origin", remote_url)?;
+
+        let mut server = mockito::Server::new();
+        std::env::set_var("GITHUB_API_BASE_URL", server.url());
+        let _get_mock = server
+            .mock("GET", "/repos/owner/old-name")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "name": "old-name",
+                    "full_name": "owner/old-name",
+                    "clone_url": "https://github.com/owner/old-name.git",
+                    "has_pages": true,
+                    "forks_count": 0
+                })
+                .to_string(),
+            )
+            .create();
+
+        let mut declined = std::io::Cursor::new("n\n");
+        let (output, result) = test_helpers::capture_stdout(|| {
+            Ok(check_rename_safety(
+                "owner",
+                "old-name",
+                false,
+                &mut declined,
+            ))
+        })?;
+        assert!(
+            output.contains("GitHub Pages is enabled for 'owner/old-name'"),
+            "Expected GitHub Pages warning, got: {}",
+            output
+        );
+        match result {
+            Err(e) => assert!(
+                e.to_string().contains("Push aborted by safety check"),
+                "Expected safety check abort, got: {}",
+                e
+            ),
+            Ok(_) => panic!("Expected push to be aborted"),
+        }
+
+        let mut accepted = std::io::Cursor::new("y\n");
+        check_rename_safety("owner", "old-name", false, &mut accepted)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,91 @@
+use crate::{
+    config::{self, PullNameSource},
+    remotes::{
+        github::{client, operations, url},
+        RemoteProvider, ResolvedRepo,
+    },
+    types::{PushOptions, RemoteKind, Result},
+};
+use git2::Repository;
+
+/// Runs `f` with the token and API endpoint configured for the remote's host in a
+/// `[github "HOST"]` section, if any.
+pub fn with_host_config<R>(remote_url: &str, f: impl FnOnce() -> R) -> R {
+    match url::parse_github_url(remote_url) {
+        Ok((host, _, _)) => config::with_config(&config::current().for_github_host(&host), f),
+        Err(_) => f(),
+    }
+}
+
+pub struct GitHubProvider {
+    /// Where the canonical name comes from; None uses the configured value.
+    pub name_source: Option<PullNameSource>,
+}
+
+impl GitHubProvider {
+    /// Resolves the name once the remote's host settings are in effect.
+    fn resolve_on_host(&self, remote_url: &str) -> Result<ResolvedRepo> {
+        let (_, owner, remote_repo_name) = url::parse_github_url(remote_url)?;
+        let repo_info = client::get_repo_info(&owner, &remote_repo_name)?;
+
+        // Owners are case-insensitive, so an owner that differs from the API's only
+        // in case names the same account and is kept as written in the URL
+        let api_owner = repo_info.full_name.split('/').next().unwrap_or(&owner);
+        let resolved_owner = if api_owner.eq_ignore_ascii_case(&owner) {
+            owner.clone()
+        } else {
+            api_owner.to_string()
+        };
+
+        // GitHub redirects API requests for renamed or transferred repositories,
+        // so full_name reflects the canonical location rather than the requested one.
+        let requested_full_name = format!("{}/{}", owner, remote_repo_name);
+        let moved_from = (format!("{}/{}", resolved_owner, repo_info.name) != requested_full_name)
+            .then_some(requested_full_name);
+
+        let name_source = match self.name_source {
+            Some(source) => source,
+            None => config::current().get_pull_name_source()?,
+        };
+        // The URL slug is only kept when it names the same repository as the API
+        // (i.e. differs only in case); after a real rename the API name wins.
+        let name = match name_source {
+            PullNameSource::Url if remote_repo_name.eq_ignore_ascii_case(&repo_info.name) => {
+                remote_repo_name
+            }
+            _ => repo_info.name,
+        };
+
+        Ok(ResolvedRepo {
+            full_name: format!("{}/{}", resolved_owner, name),
+            name,
+            location: repo_info.clone_url,
+            moved_from,
+        })
+    }
+}
+
+impl RemoteProvider for GitHubProvider {
+    fn kind(&self) -> RemoteKind {
+        RemoteKind::GitHub
+    }
+
+    fn resolve_repo_name(&self, remote_url: &str) -> Result<ResolvedRepo> {
+        with_host_config(remote_url, || self.resolve_on_host(remote_url))
+    }
+
+    fn format_remote_url(&self, remote_url: &str, resolved: &ResolvedRepo) -> Result<String> {
+        let owner = resolved.full_name.split('/').next().unwrap_or_default();
+        Ok(url::format_new_remote_url(
+            remote_url,
+            owner,
+            &resolved.name,
+        ))
+    }
+
+    fn push(&self, repo: &Repository, remote_url: &str, options: &PushOptions) -> Result<()> {
+        with_host_config(remote_url, || {
+            operations::push_to_github_remote(repo, remote_url, options)
+        })
+    }
+}
@@ -0,0 +1,662 @@
+use crate::{
+    config::{self, ForgeKind, ForgeProvider, PullNameSource},
+    git,
+    progress::{ProgressSink, SyncOutcome, SyncPart, TextSink},
+    types::{AlignOptions, Error, PullOptions, PushOptions, RemoteKind, Result},
+    utils::{prompt, shell},
+};
+use git2::Repository;
+use log::{debug, warn};
+use std::io::BufRead;
+use std::path::PathBuf;
+
+pub mod file {
+    pub mod operations;
+    pub mod provider;
+    pub mod url;
+}
+pub mod gitea {
+    pub mod client;
+    pub mod provider;
+    pub mod url;
+}
+pub mod github {
+    pub mod client;
+    pub mod operations;
+    pub mod provider;
+    pub mod url;
+}
+
+/// The canonical identity of a repository as reported by its remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRepo {
+    /// Canonical repository name, which the local directory should match.
+    pub name: String,
+    /// `owner/name` for hosted remotes, the bare name for file remotes.
+    pub full_name: String,
+    /// Canonical location: the clone URL for hosted remotes, a `file://` URL for file remotes.
+    pub location: String,
+    /// The `full_name` that was requested, when the host redirected to a different one.
+    pub moved_from: Option<String>,
+}
+
+/// A backend hosting the remote repository (GitHub, a file path, ...).
+pub trait RemoteProvider {
+    /// Which kind of remote this provider handles.
+    fn kind(&self) -> RemoteKind;
+
+    /// Resolves the canonical repository name and location for the remote URL.
+    fn resolve_repo_name(&self, remote_url: &str) -> Result<ResolvedRepo>;
+
+    /// Formats a remote URL pointing at the resolved repository, keeping the style of
+    /// the original remote URL.
+    fn format_remote_url(&self, remote_url: &str, resolved: &ResolvedRepo) -> Result<String>;
+
+    /// Renames the remote repository to match the local directory name.
+    fn push(&self, repo: &Repository, remote_url: &str, options: &PushOptions) -> Result<()>;
+}
+
+/// Picks the provider that handles the given remote URL: a self-hosted forge
+/// configured for its host, then GitHub, falling back to file remotes.
+/// `name_source` only applies to GitHub remotes; None uses the configured value.
+/// `offline` only applies to file remotes.
+pub fn provider_for_url(
+    remote_url: &str,
+    name_source: Option<PullNameSource>,
+    offline: bool,
+) -> Box<dyn RemoteProvider> {
+    if let Some(forge) = configured_forge(remote_url) {
+        debug!("Using provider '{}' for {}", forge.name, forge.host);
+        return match forge.kind {
+            ForgeKind::Gitea => Box::new(gitea::provider::GiteaProvider { forge }),
+        };
+    }
+
+    if let Some(warning) = github::url::host_mismatch_warning(remote_url) {
+        warn!("{}", warning);
+    }
+
+    if github::url::is_github_url(remote_url) {
+        Box::new(github::provider::GitHubProvider { name_source })
+    } else {
+        Box::new(file::provider::FileProvider { offline })
+    }
+}
+
+/// The `[provider.NAME]` section whose host the remote URL points at.
+pub(crate) fn configured_forge(remote_url: &str) -> Option<ForgeProvider> {
+    let (host, _, _) = gitea::url::parse_forge_url(remote_url)?;
+    config::current().get_provider_for_host(&host)
+}
+
+/// Checks that a user-supplied URL is a GitHub URL, a URL on a configured forge, an
+/// SSH URL or a path to an existing repository, so typos fail early instead of
+/// surfacing as filesystem errors.
+pub fn validate_remote_url(remote_url: &str) -> Result<String> {
+    if github::url::is_github_url(remote_url)
+        || configured_forge(remote_url).is_some()
+        || file::url::ssh_remote_path(remote_url).is_some()
+        || crate::utils::fs::resolve_canonical_path(std::path::Path::new(remote_url)).is_ok()
+    {
+        Ok(remote_url.to_string())
+    } else {
+        Err(Error::InvalidRemoteUrl(remote_url.to_string()))
+    }
+}
+
+/// The changes `pull` would make to bring a repository in line with its remote.
+#[derive(Debug, Clone)]
+pub struct PullPlan {
+    pub kind: RemoteKind,
+    pub resolved: ResolvedRepo,
+    pub remote_name: String,
+    pub remote_url: String,
+    pub workdir: PathBuf,
+    /// The new remote URL, when it changes.
+    pub new_remote_url: Option<String>,
+    /// The new directory name, when it changes.
+    pub new_directory_name: Option<String>,
+}
+
+impl PullPlan {
+    /// Drops the changes that `--no-rename-remote`/`--no-rename-dir`/`--keep-relative`
+    /// opt out of.
+    pub fn limit_to(mut self, options: &PullOptions) -> Self {
+        if options.no_rename_remote {
+            self.new_remote_url = None;
+        }
+        // A relative file remote resolved to the repository, so only its spelling
+        // would change
+        if options.keep_relative
+            && self.kind == RemoteKind::File
+            && file::url::is_relative(&self.remote_url)
+        {
+            self.new_remote_url = None;
+        }
+        if options.no_rename_dir {
+            self.new_directory_name = None;
+        }
+        self
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.new_remote_url.is_some() || self.new_directory_name.is_some()
+    }
+
+    /// Shell commands that would apply the plan by hand.
+    pub fn commands(&self) -> Vec<String> {
+        let workdir = self.workdir.to_string_lossy();
+        let workdir = workdir.trim_end_matches('/');
+        let mut commands = Vec::new();
+
+        if let Some(new_remote_url) = &self.new_remote_url {
+            commands.push(format!(
+                "git -C {} remote set-url {} {}",
+                shell::quote(workdir),
+                shell::quote(&self.remote_name),
+                shell::quote(new_remote_url)
+            ));
+        }
+
+        if let Some(new_directory_name) = &self.new_directory_name {
+            let new_workdir = self
+                .workdir
+                .parent()
+                .map(|parent| parent.join(new_directory_name))
+                .unwrap_or_else(|| PathBuf::from(new_directory_name));
+            commands.push(format!(
+                "mv {} {}",
+                shell::quote(workdir),
+                shell::quote(&new_workdir.to_string_lossy())
+            ));
+        }
+
+        commands
+    }
+}
+
+/// Computes what `pull` would change without touching anything.
+pub fn plan_pull(
+    provider: &dyn RemoteProvider,
+    repo: &Repository,
+    remote_url: &str,
+    remote: Option<&str>,
+) -> Result<PullPlan> {
+    let local_directory_name = git::get_local_directory_name(repo)?;
+    let resolved = provider.resolve_repo_name(remote_url)?;
+    let resolved_remote_url = provider.format_remote_url(remote_url, &resolved)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| Error::Fs("Cannot get repository working directory".into()))?
+        .to_path_buf();
+
+    // `--remote-url` only changes where the name is looked up: a URL that isn't
+    // the remote's own is never written into the remote
+    let remote_name = git::select_remote_name(repo, remote)?;
+    let is_own_url = is_remote_own_url(repo, remote, remote_url);
+    // `insteadOf` rewrites only matter for classifying the remote; it keeps the
+    // spelling it was configured with
+    let (remote_url, resolved_remote_url) = match git::configured_remote_url(repo, &remote_name) {
+        Some(configured) if is_own_url => (
+            configured,
+            git::unapply_instead_of(repo, &remote_name, &resolved_remote_url),
+        ),
+        _ => (remote_url.to_string(), resolved_remote_url),
+    };
+    let should_change_remote = is_own_url && resolved_remote_url != remote_url;
+    let should_rename_directory = local_directory_name != resolved.name;
+    debug!(
+        "Resolved '{}' to '{}' ({}): should_change_remote={}, should_rename_directory={}",
+        git::redact_credentials(&remote_url),
+        resolved.full_name,
+        resolved.location,
+        should_change_remote,
+        should_rename_directory
+    );
+
+    Ok(PullPlan {
+        kind: provider.kind(),
+        new_remote_url: should_change_remote.then_some(resolved_remote_url),
+        new_directory_name: should_rename_directory.then(|| resolved.name.clone()),
+        resolved,
+        remote_name,
+        remote_url,
+        workdir,
+    })
+}
+
+/// Whether `remote_url` is the URL the selected remote points at, rather than a
+/// `--remote-url` override naming some other repository.
+fn is_remote_own_url(repo: &Repository, remote: Option<&str>, remote_url: &str) -> bool {
+    git::selected_remote_url(repo, remote).is_ok_and(|url| url == remote_url)
+}
+
+/// Points the remote at the repository `push` renamed. When `remote_url` was a
+/// `--remote-url` override for some other repository, the remote is left alone.
+pub(crate) fn set_pushed_remote_url(
+    repo: &Repository,
+    remote_url: &str,
+    new_remote_url: &str,
+    dry_run: bool,
+) -> Result<()> {
+    if !is_remote_own_url(repo, None, remote_url) {
+        debug!(
+            "Leaving the remote alone: '{}' is not its URL",
+            git::redact_credentials(remote_url)
+        );
+        return Ok(());
+    }
+    // Keep the spelling the remote is configured with, before `insteadOf` rewrites
+    let remote_name = git::get_remote_name(repo)?;
+    let current_url =
+        git::configured_remote_url(repo, &remote_name).unwrap_or_else(|| remote_url.to_string());
+    let new_remote_url = git::unapply_instead_of(repo, &remote_name, new_remote_url);
+    git::set_remote_url(repo, &current_url, &new_remote_url, dry_run)
+}
+
+/// Renames the local directory and rewrites the remote URL to match the canonical
+/// repository reported by the provider, reporting each change to `sink`.
+///
+/// Returns whether the directory name or remote URL was (or, in dry-run mode,
+/// would be) changed.
+pub fn pull(
+    provider: &dyn RemoteProvider,
+    repo: &Repository,
+    remote_url: &str,
+    options: &PullOptions,
+    sink: &dyn ProgressSink,
+) -> Result<bool> {
+    let dry_run = options.dry_run;
+    let full_plan = plan_pull(provider, repo, remote_url, options.remote.as_deref())?;
+    // Judge what's already in sync before --no-rename-* hides the other change
+    let in_sync_part = match (&full_plan.new_directory_name, &full_plan.new_remote_url) {
+        (None, Some(_)) => Some(SyncPart::Directory),
+        (Some(_), None) => Some(SyncPart::RemoteUrl),
+        _ => None,
+    };
+    let plan = full_plan.limit_to(options);
+
+    if let Some(moved_from) = &plan.resolved.moved_from {
+        sink.on_change(&SyncOutcome::Moved {
+            from: moved_from.clone(),
+            to: plan.resolved.full_name.clone(),
+        });
+    }
+
+    if !plan.has_changes() {
+        if !options.quiet {
+            sink.on_change(&SyncOutcome::UpToDate);
+        }
+        return Ok(false);
+    }
+
+    if plan.new_directory_name.is_some() && !dry_run && !options.force {
+        git::check_clean_worktree(repo)?;
+    }
+
+    if let Some(part) = in_sync_part {
+        sink.on_change(&SyncOutcome::PartiallyInSync { part, dry_run });
+    }
+
+    if let Some(new_remote_url) = &plan.new_remote_url {
+        if !dry_run {
+            git::update_remote_url(repo, &plan.remote_name, new_remote_url)?;
+        }
+        sink.on_change(&SyncOutcome::RemoteUrl {
+            remote: plan.remote_name.clone(),
+            from: plan.remote_url.clone(),
+            to: new_remote_url.clone(),
+            dry_run,
+        });
+    }
+
+    if let Some(new_directory_name) = &plan.new_directory_name {
+        let submodule = match repo.workdir() {
+            Some(workdir) => git::find_submodule_ref(workdir)?,
+            None => None,
+        };
+
+        git::rename_workdir(repo, new_directory_name, dry_run, sink)?;
+
+        match submodule {
+            Some(submodule) if options.update_submodule_ref => {
+                if !dry_run {
+                    git::update_submodule_ref(&submodule, new_directory_name)?;
+                }
+                sink.on_change(&SyncOutcome::SubmoduleRef {
+                    superproject: submodule.superproject,
+                    from: submodule.path,
+                    to: new_directory_name.clone(),
+                    dry_run,
+                });
+            }
+            Some(submodule) => warn!(
+                "'{}' is a submodule of '{}'; pass --update-submodule-ref to update its path there",
+                submodule.path,
+                submodule.superproject.display()
+            ),
+            None => {}
+        }
+    }
+
+    Ok(true)
+}
+
+/// Brings the directory name and remote URL in line with the remote in one pass,
+/// showing every change up front and asking for a single confirmation.
+///
+/// Returns whether anything was (or, in dry-run mode, would be) changed.
+pub fn align(
+    provider: &dyn RemoteProvider,
+    repo: &Repository,
+    remote_url: &str,
+    options: &AlignOptions,
+    input: &mut dyn BufRead,
+) -> Result<bool> {
+    let plan = plan_pull(provider, repo, remote_url, None)?;
+
+    if !plan.has_changes() {
+        println!(
+            "Already aligned: directory name and remote URL match '{}'",
+            plan.resolved.full_name
+        );
+        return Ok(false);
+    }
+
+    let count = plan.new_remote_url.iter().count() + plan.new_directory_name.iter().count();
+    println!(
+        "Aligning {} thing(s) with '{}':",
+        count, plan.resolved.full_name
+    );
+    if let Some(new_remote_url) = &plan.new_remote_url {
+        println!(
+            "  '{}' remote: {} -> {}",
+            plan.remote_name,
+            git::redact_credentials(&plan.remote_url),
+            git::redact_credentials(new_remote_url)
+        );
+    }
+    if let Some(new_directory_name) = &plan.new_directory_name {
+        println!(
+            "  directory: {} -> {}",
+            git::get_local_directory_name(repo)?,
+            new_directory_name
+        );
+    }
+
+    if options.dry_run {
+        return Ok(true);
+    }
+
+    if plan.new_directory_name.is_some() && !options.force {
+        git::check_clean_worktree(repo)?;
+    }
+
+    if !options.yes && !prompt::confirm(input, "Apply these changes?")? {
+        return Err(Error::Config("Align aborted".into()));
+    }
+
+    if let Some(new_remote_url) = &plan.new_remote_url {
+        git::set_remote_url(repo, &plan.remote_url, new_remote_url, false)?;
+    }
+    if let Some(new_directory_name) = &plan.new_directory_name {
+        git::rename_workdir(repo, new_directory_name, false, &TextSink)?;
+    }
+
+    Ok(true)
+}
+
+/// The name `push` renames the remote repository to: `--name` when given,
+/// otherwise the local directory name.
+pub fn push_target_name(repo: &Repository, options: &PushOptions) -> Result<String> {
+    match &options.name {
+        Some(name) => Ok(name.clone()),
+        None => git::get_local_directory_name(repo),
+    }
+}
+
+/// Renames the remote repository through the provider, after rejecting options
+/// the provider can't honor.
+pub fn push(
+    provider: &dyn RemoteProvider,
+    repo: &Repository,
+    remote_url: &str,
+    options: &PushOptions,
+) -> Result<()> {
+    if options.name.is_some() && provider.kind() == RemoteKind::File {
+        return Err(Error::Config(
+            "--name is only supported for hosted remotes; rename the local directory instead"
+                .into(),
+        ));
+    }
+    if (options.description.is_some() || !options.topics.is_empty())
+        && provider.kind() != RemoteKind::GitHub
+    {
+        return Err(Error::Config(
+            "--description and --topic are only supported for GitHub remotes".into(),
+        ));
+    }
+
+    provider.push(repo, remote_url, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers;
+
+    #[test]
+    fn test_providers_as_trait_objects() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let bare_repo_path = test_helpers::create_bare_repo(&temp, "file-repo.git")?;
+        let file_url = test_helpers::get_canonical_remote_url(&bare_repo_path)?;
+
+        let cases: Vec<(&str, &str, &str)> = vec![
+            (
+                "git@github.com:owner/old-name.git",
+                "new-name",
+                "git@github.com:owner/new-name.git",
+            ),
+            (&file_url, "file-repo", &file_url),
+        ];
+
+        for (remote_url, expected_name, expected_url) in cases {
+            let provider: Box<dyn RemoteProvider> = provider_for_url(remote_url, None, false);
+            let resolved = provider.resolve_repo_name(remote_url)?;
+            assert_eq!(resolved.name, expected_name);
+            assert_eq!(
+                provider.format_remote_url(remote_url, &resolved)?,
+                expected_url
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_description_to_file_remote() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        let bare_repo_path = test_helpers::create_bare_repo(&temp, "old-name.git")?;
+        let (_, repo) = test_helpers::create_main_repo(&temp, "new-name")?;
+        let remote_url = bare_repo_path.display().to_string();
+        repo.remote("origin", &remote_url)?;
+
+        let provider = provider_for_url(&remote_url, None, false);
+        let result = push(
+            provider.as_ref(),
+            &repo,
+            &remote_url,
+            &PushOptions {
+                description: Some("A new description".to_string()),
+                ..Default::default()
+            },
+        );
+
+        // Nothing is renamed when part of the request can't be honored
+        assert!(matches!(result, Err(Error::Config(_))), "{:?}", result);
+        assert!(bare_repo_path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_pull_commands() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "old-name")?;
+        std::env::set_current_dir(&repo_dir)?;
+        let remote_url = "git@github.com:owner/old-name.git";
+        repo.remote("origin", remote_url)?;
+
+        let provider = provider_for_url(remote_url, None, false);
+        let plan = plan_pull(provider.as_ref(), &repo, remote_url, None)?;
+        let workdir = repo_dir.display().to_string();
+        let new_workdir = temp.path().join("new-name").display().to_string();
+
+        assert!(plan.has_changes());
+        assert_eq!(
+            plan.commands(),
+            vec![
+                format!(
+                    "git -C {} remote set-url origin git@github.com:owner/new-name.git",
+                    shell::quote(&workdir)
+                ),
+                format!(
+                    "mv {} {}",
+                    shell::quote(&workdir),
+                    shell::quote(&new_workdir)
+                ),
+            ]
+        );
+        assert_eq!(remote_url, git::get_remote_url(&repo)?);
+        test_helpers::assert_directory_existence(&temp, "old-name", true)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_combined_changes() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "local-name")?;
+        std::env::set_current_dir(&repo_dir)?;
+        let remote_url = "https://github.com/owner/old-name.git";
+        repo.remote("origin", remote_url)?;
+
+        let provider = provider_for_url(remote_url, None, false);
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            align(
+                provider.as_ref(),
+                &repo,
+                remote_url,
+                &AlignOptions::default(),
+                &mut std::io::Cursor::new("y\n"),
+            )
+        })?;
+
+        assert!(changed);
+        assert!(
+            output.contains("Aligning 2 thing(s) with 'owner/new-name':"),
+            "Expected combined summary, got: {}",
+            output
+        );
+        let repo = Repository::open(temp.path().join("new-name"))?;
+        assert_eq!(
+            "https://github.com/owner/new-name.git",
+            git::get_remote_url(&repo)?
+        );
+        test_helpers::assert_directory_existence(&temp, "local-name", false)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_keeps_configured_url_spelling() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "old-name", "new-name");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "new-name")?;
+        std::env::set_current_dir(&repo_dir)?;
+        repo.remote("origin", "https://github.com/owner/old-name.git")?;
+        repo.config()?
+            .set_str("url.git@github.com:.insteadOf", "https://github.com/")?;
+        let remote_url = git::get_remote_url(&repo)?;
+        assert_eq!(remote_url, "git@github.com:owner/old-name.git");
+
+        let provider = provider_for_url(&remote_url, None, false);
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            align(
+                provider.as_ref(),
+                &repo,
+                &remote_url,
+                &AlignOptions {
+                    yes: true,
+                    ..Default::default()
+                },
+                &mut std::io::Cursor::new(""),
+            )
+        })?;
+
+        // git connects over SSH, but the remote stays spelled as configured
+        assert!(changed);
+        assert!(
+            output.contains(
+                "'origin' remote: https://github.com/owner/old-name.git -> https://github.com/owner/new-name.git"
+            ),
+            "Expected configured spelling in summary, got: {}",
+            output
+        );
+        assert!(
+            output.contains("from 'https://github.com/owner/old-name.git'"),
+            "{}",
+            output
+        );
+        assert_eq!(
+            repo.config()?.get_string("remote.origin.url")?,
+            "https://github.com/owner/new-name.git"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_already_aligned() -> anyhow::Result<()> {
+        let _guard = test_helpers::CurrentDirGuard::new();
+        let temp = assert_fs::TempDir::new()?;
+        test_helpers::setup_test_config(temp.path())?;
+        test_helpers::mock_github_get_repo("owner", "owner", "repo", "repo");
+        let (repo_dir, repo) = test_helpers::create_main_repo(&temp, "repo")?;
+        std::env::set_current_dir(&repo_dir)?;
+        let remote_url = "git@github.com:owner/repo.git";
+        repo.remote("origin", remote_url)?;
+
+        let provider = provider_for_url(remote_url, None, false);
+        // No confirmation is read when there is nothing to do
+        let (output, changed) = test_helpers::capture_stdout(|| {
+            align(
+                provider.as_ref(),
+                &repo,
+                remote_url,
+                &AlignOptions::default(),
+                &mut std::io::Cursor::new(""),
+            )
+        })?;
+
+        assert!(!changed);
+        assert!(
+            output.contains("Already aligned: directory name and remote URL match 'owner/repo'"),
+            "Expected no-op message, got: {}",
+            output
+        );
+
+        Ok(())
+    }
+}
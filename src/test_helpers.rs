@@ -5,7 +5,6 @@ use assert_fs::prelude::*;
 use assert_fs::TempDir;
 use gag::BufferRedirect;
 use ini::Ini;
-use mockito;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
@@ -23,6 +22,8 @@ pub fn setup_test_config(temp_dir: &Path) -> anyhow::Result<()> {
         .set("default_remote", "origin");
     conf.write_to_file(&config_file)?;
 
+    crate::remotes::github::client::clear_repo_info_cache();
+
     Ok(())
 }
 
@@ -117,6 +118,28 @@ pub fn mock_github_get_repo(
     std::mem::forget(server);
 }
 
+/// Mocks looking up an existing, unarchived repository on `server`, as `push`
+/// does before renaming it.
+pub fn mock_github_existing_repo(
+    server: &mut mockito::ServerGuard,
+    owner: &str,
+    repo: &str,
+) -> mockito::Mock {
+    server
+        .mock("GET", format!("/repos/{}/{}", owner, repo).as_str())
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            serde_json::json!({
+                "name": repo,
+                "full_name": format!("{}/{}", owner, repo),
+                "clone_url": format!("https://github.com/{}/{}.git", owner, repo)
+            })
+            .to_string(),
+        )
+        .create()
+}
+
 /// Mock GitHub API error response.
 pub fn mock_github_get_repo_error(owner: &str, repo: &str) {
     let mut server = mockito::Server::new();
@@ -133,13 +156,31 @@ pub fn mock_github_get_repo_error(owner: &str, repo: &str) {
     std::mem::forget(server);
 }
 
-/// Mock GitHub API repository update response.
+/// Mock GitHub API 401 response for an invalid or expired token.
+pub fn mock_github_get_repo_unauthorized(owner: &str, repo: &str) {
+    let mut server = mockito::Server::new();
+    std::env::set_var("GITHUB_API_BASE_URL", server.url());
+
+    let _mock = server
+        .mock("GET", format!("/repos/{}/{}", owner, repo).as_str())
+        .with_status(401)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"message": "Bad credentials"}"#)
+        .create();
+
+    // Server will be kept alive until it goes out of scope at the end of the test
+    std::mem::forget(server);
+}
+
+/// Mock GitHub API repository update response. The target name is reported as free
+/// (404) so the pre-rename collision check passes. Returns the GET (of the target
+/// name) and PATCH mocks.
 pub fn mock_github_update_repo(
     old_owner: &str,
     new_owner: &str,
     old_repo_name: &str,
     new_repo_name: &str,
-) {
+) -> (mockito::Mock, mockito::Mock) {
     let mut server = mockito::Server::new();
     std::env::set_var("GITHUB_API_BASE_URL", server.url());
 
@@ -149,7 +190,18 @@ pub fn mock_github_update_repo(
         "clone_url": format!("https://github.com/{}/{}.git", new_owner, new_repo_name)
     });
 
-    let _mock = server
+    mock_github_existing_repo(&mut server, old_owner, old_repo_name);
+    let get_mock = server
+        .mock(
+            "GET",
+            format!("/repos/{}/{}", old_owner, new_repo_name).as_str(),
+        )
+        .with_status(404)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"message": "Not Found"}"#)
+        .create();
+
+    let patch_mock = server
         .mock(
             "PATCH",
             format!("/repos/{}/{}", old_owner, old_repo_name).as_str(),
@@ -161,6 +213,7 @@ pub fn mock_github_update_repo(
 
     // Server will be kept alive until it goes out of scope at the end of the test
     std::mem::forget(server);
+    (get_mock, patch_mock)
 }
 
 /// Mock GitHub API error response for PATCH requests (used for repository updates).
@@ -177,6 +230,15 @@ pub fn mock_github_update_repo_error(owner: &str, repo: &str, status: usize) {
         _ => r#"{"message": "Failed to update repository name"}"#,
     };
 
+    // Any target name is reported as free so the request reaches the PATCH
+    mock_github_existing_repo(&mut server, owner, repo);
+    let _get_mock = server
+        .mock("GET", mockito::Matcher::Any)
+        .with_status(404)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"message": "Not Found"}"#)
+        .create();
+
     let _mock = server
         .mock("PATCH", format!("/repos/{}/{}", owner, repo).as_str())
         .with_status(status)
@@ -188,6 +250,38 @@ pub fn mock_github_update_repo_error(owner: &str, repo: &str, status: usize) {
     std::mem::forget(server);
 }
 
+/// Mock GitHub API repository description update. The mock only matches a PATCH
+/// whose body sets the given description, so asserting it verifies the request body.
+pub fn mock_github_update_repo_description(
+    owner: &str,
+    repo_name: &str,
+    description: &str,
+) -> mockito::Mock {
+    let mut server = mockito::Server::new();
+    std::env::set_var("GITHUB_API_BASE_URL", server.url());
+
+    let response_body = serde_json::json!({
+        "name": repo_name,
+        "full_name": format!("{}/{}", owner, repo_name),
+        "clone_url": format!("https://github.com/{}/{}.git", owner, repo_name),
+        "description": description
+    });
+
+    let mock = server
+        .mock("PATCH", format!("/repos/{}/{}", owner, repo_name).as_str())
+        .match_body(mockito::Matcher::Json(
+            serde_json::json!({ "description": description }),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(response_body.to_string())
+        .create();
+
+    // Server will be kept alive until it goes out of scope at the end of the test
+    std::mem::forget(server);
+    mock
+}
+
 /// Helper to check if directory exists or not
 pub fn assert_directory_existence(
     temp: &TempDir,
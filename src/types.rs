@@ -1,17 +1,65 @@
+use crate::config::PullNameSource;
+use std::time::Duration;
+
+/// Errors returned by the library. The CLI exits with [`Error::exit_code`] so
+/// scripts can tell failure kinds apart:
+///
+/// | Code | Variants                                                           |
+/// | ---- | ------------------------------------------------------------------ |
+/// | 1    | `Other`                                                            |
+/// | 3    | `NotAGitRepo`, `StaleWorkingDirectory`                             |
+/// | 4    | `NoRemote`, `UnknownRemote`, `EmptyRemoteUrl`                      |
+/// | 5    | `InvalidGitHubUrl`, `InvalidRemoteUrl`                             |
+/// | 6    | `GitHubApi`, `AuthenticationFailed`, `PermissionDenied`,           |
+/// |      | `RateLimited`, `ForgeApi`                                          |
+/// | 7    | `Fs`, `Io`                                                         |
+/// | 8    | `Config`                                                           |
+///
+/// Exit code 2 is reserved for `--dry-run` reporting that something is out of sync.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Error: not a git repository")]
     NotAGitRepo,
 
+    #[error("Error: the current directory no longer exists (it or a parent directory may have been renamed). Please cd into the repository directory again")]
+    StaleWorkingDirectory,
+
     #[error("Error: no remote named '{0}' configured")]
     NoRemote(String),
 
+    #[error("Error: no remote named '{0}' configured. Available remotes: {1}")]
+    UnknownRemote(String, String),
+
+    #[error("Error: remote '{0}' has no URL. Set one with `git remote set-url {0} <url>`")]
+    EmptyRemoteUrl(String),
+
+    #[error("Error: '{0}' is not a GitHub URL or an existing repository path")]
+    InvalidRemoteUrl(String),
+
     #[error("Invalid GitHub URL format: {0}")]
     InvalidGitHubUrl(String),
 
     #[error("GitHub API error: {0}")]
     GitHubApi(String),
 
+    /// GitHub answered 401: the token is invalid or expired.
+    #[error("GitHub API error: Authentication failed: your GitHub token is invalid or expired. Update it with 'git-repo-name config github-token YOUR_TOKEN'")]
+    AuthenticationFailed,
+
+    /// GitHub refused a write with a 403 for `reason`.
+    #[error("GitHub API error: {message}")]
+    PermissionDenied {
+        reason: DenialReason,
+        message: String,
+    },
+
+    #[error("GitHub API error: rate limit exceeded. {}", retry_hint(.retry_after))]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// An error from a self-hosted forge configured in a `[provider.NAME]` section.
+    #[error("{0} API error: {1}")]
+    ForgeApi(String, String),
+
     #[error("Error: {0}")]
     Config(String),
 
@@ -25,4 +73,201 @@ pub enum Error {
     Other(#[from] anyhow::Error),
 }
 
+impl Error {
+    /// The process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotAGitRepo | Error::StaleWorkingDirectory => 3,
+            Error::NoRemote(_) | Error::UnknownRemote(_, _) | Error::EmptyRemoteUrl(_) => 4,
+            Error::InvalidGitHubUrl(_) | Error::InvalidRemoteUrl(_) => 5,
+            Error::GitHubApi(_)
+            | Error::AuthenticationFailed
+            | Error::PermissionDenied { .. }
+            | Error::RateLimited { .. }
+            | Error::ForgeApi(_, _) => 6,
+            Error::Fs(_) | Error::Io(_) => 7,
+            Error::Config(_) => 8,
+            Error::Other(_) => 1,
+        }
+    }
+}
+
+fn retry_hint(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(delay) => format!("Retry in {} seconds.", delay.as_secs().max(1)),
+        None => "Retry later.".to_string(),
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why GitHub answered a write with 403, which it uses for several unrelated
+/// problems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenialReason {
+    /// The token isn't authorized for the organization's SAML single sign-on.
+    SsoRequired,
+    TokenExpired,
+    /// The token lacks a permission the request needs.
+    MissingPermission,
+}
+
+/// The kind of remote a repository name was resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteKind {
+    GitHub,
+    File,
+    /// A self-hosted Gitea instance configured in a `[provider.NAME]` section.
+    Gitea,
+}
+
+/// A repository name resolved from its remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoName {
+    /// Canonical repository name.
+    pub name: String,
+    /// `owner/name` for hosted remotes, the bare name for file remotes.
+    pub full_name: String,
+    /// The owning user or organization of a hosted repository.
+    pub owner: Option<String>,
+    /// Where to clone the repository from: the GitHub clone URL or a `file://` URL.
+    pub clone_url: String,
+    pub source: RemoteKind,
+    /// `owner/name` the remote URL asked for, when the remote redirected it to a
+    /// renamed or transferred repository.
+    pub moved_from: Option<String>,
+}
+
+/// Options for the `pull` command.
+#[derive(Debug, Default, Clone)]
+pub struct PullOptions {
+    pub dry_run: bool,
+    /// Use this remote instead of the configured one.
+    pub remote: Option<String>,
+    /// Overrides the configured `pull-name-source` for this run.
+    pub name_source: Option<PullNameSource>,
+    /// Print the shell commands that would apply the changes instead of running them.
+    pub print_commands: bool,
+    /// Rename the directory even if the working tree has uncommitted changes.
+    pub force: bool,
+    /// Use this URL instead of the configured remote's URL.
+    pub remote_url: Option<String>,
+    /// Take file remotes' names from the URL as written, without requiring the path
+    /// to exist. The remote URL is left as is.
+    pub offline: bool,
+    /// Leave the remote URL alone and only rename the directory.
+    pub no_rename_remote: bool,
+    /// Keep a relative file remote URL as written instead of rewriting it to the
+    /// absolute path it resolves to.
+    pub keep_relative: bool,
+    /// Leave the directory alone and only update the remote URL.
+    pub no_rename_dir: bool,
+    /// When the repository is a submodule, also update its path in the
+    /// superproject's `.gitmodules` and index.
+    pub update_submodule_ref: bool,
+    /// Don't report repositories that are already up-to-date.
+    pub quiet: bool,
+    /// Add the remote with `remote_url` when the repository doesn't have it yet.
+    pub create_remote: bool,
+}
+
+/// Options for the `align` command.
+#[derive(Debug, Default, Clone)]
+pub struct AlignOptions {
+    pub dry_run: bool,
+    /// Apply the changes without asking for confirmation.
+    pub yes: bool,
+    /// Rename the directory even if the working tree has uncommitted changes.
+    pub force: bool,
+    /// Use this URL instead of the configured remote's URL.
+    pub remote_url: Option<String>,
+}
+
+/// Options for the `push` command.
+#[derive(Debug, Default, Clone)]
+pub struct PushOptions {
+    pub dry_run: bool,
+    /// New repository description to set on the remote, if any.
+    pub description: Option<String>,
+    /// Topics to set on the remote. Empty means leave topics untouched.
+    pub topics: Vec<String>,
+    /// Branch to make the remote's default branch, if any. It must already exist.
+    pub default_branch: Option<String>,
+    /// Check for GitHub Pages and forks before renaming and ask for confirmation.
+    pub safety_check: bool,
+    /// Transfer the repository to this owner (user or organization).
+    pub owner: Option<String>,
+    /// Use this URL instead of the configured remote's URL.
+    pub remote_url: Option<String>,
+    /// Check that the GitHub token is valid before changing anything.
+    pub verify_token: bool,
+    /// Rename the remote repository to this instead of the local directory name.
+    pub name: Option<String>,
+}
+
+/// Options for the `clone` command.
+#[derive(Debug, Default, Clone)]
+pub struct CloneOptions {
+    /// The remote URL to clone.
+    pub url: String,
+    /// Print what would be cloned where instead of cloning.
+    pub dry_run: bool,
+}
+
+/// Options for the `fetch` command.
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptions {
+    /// Print `GRN_REPO_NAME=...` and `GRN_REPO_URL=...` lines suitable for `eval`.
+    pub emit_env: bool,
+    /// Use this URL instead of the configured remote's URL.
+    pub remote_url: Option<String>,
+    /// Print what would be looked up instead of contacting the remote.
+    pub dry_run: bool,
+    /// Print only the name, without the URL or a trailing newline.
+    pub no_newline: bool,
+    /// Look up a repository named after the local directory under the authenticated
+    /// GitHub user, instead of resolving the remote.
+    pub by_directory: bool,
+    /// Resolve every configured remote and print a line for each.
+    pub all_remotes: bool,
+    /// Print the resolved repository through this template instead, e.g.
+    /// `{owner}/{name}`. See [`crate::format_repo_name`] for the placeholders.
+    pub format: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let cases = vec![
+            (Error::NotAGitRepo, 3),
+            (Error::StaleWorkingDirectory, 3),
+            (Error::NoRemote("origin".into()), 4),
+            (Error::UnknownRemote("origin".into(), "upstream".into()), 4),
+            (Error::EmptyRemoteUrl("origin".into()), 4),
+            (Error::InvalidGitHubUrl("url".into()), 5),
+            (Error::InvalidRemoteUrl("url".into()), 5),
+            (Error::GitHubApi("error".into()), 6),
+            (Error::AuthenticationFailed, 6),
+            (
+                Error::PermissionDenied {
+                    reason: DenialReason::MissingPermission,
+                    message: "error".into(),
+                },
+                6,
+            ),
+            (Error::RateLimited { retry_after: None }, 6),
+            (Error::ForgeApi("gitea".into(), "error".into()), 6),
+            (Error::Fs("error".into()), 7),
+            (Error::Io(std::io::Error::other("error")), 7),
+            (Error::Config("error".into()), 8),
+            (Error::Other(anyhow::anyhow!("error")), 1),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(error.exit_code(), expected, "{:?}", error);
+        }
+    }
+}
@@ -1,48 +1,224 @@
-use crate::types::{Error, Result};
+use crate::{
+    types::{Error, Result},
+    utils::color,
+};
+use log::debug;
+use path_clean::PathClean;
+use std::borrow::Cow;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Renames a directory to a new name, keeping it in the same parent directory.
 pub fn rename_directory(current_path: &Path, new_name: &str, dry_run: bool) -> Result<()> {
+    // Rename the directory itself, not a symlink pointing at it
+    check_new_name(new_name)?;
+    let current_path = &resolve_real_path(current_path)?;
+    check_renamable(current_path)?;
     let parent_path = current_path
         .parent()
         .ok_or_else(|| Error::Fs("Cannot get parent directory".into()))?;
-    let new_path = parent_path.join(new_name);
-
-    // Convert paths to strings and remove any trailing slashes for display
-    let current_display = current_path
-        .to_string_lossy()
-        .trim_end_matches('/')
-        .to_string();
-    let new_display = new_path.to_string_lossy().trim_end_matches('/').to_string();
+    let current_display = display_path(current_path);
+    let new_display = display_path(&parent_path.join(new_name));
 
     if dry_run {
         println!(
             "Would rename directory from '{}' to '{}'",
-            current_display, new_display
+            color::old(&current_display),
+            color::new(&new_display)
         );
         return Ok(());
     }
 
     println!(
         "Renaming directory from '{}' to '{}'...",
-        current_display, new_display
+        color::old(&current_display),
+        color::new(&new_display)
     );
 
+    move_directory(current_path, new_name)?;
+
+    // Output a machine-readable marker for the shell wrapper to detect
+    println!("GRN_DIR_CHANGE:{}:{}", current_display, new_display);
+
+    Ok(())
+}
+
+/// Renames a directory to a new name in the same parent directory without printing
+/// anything. Returns the new path.
+pub fn move_directory(current_path: &Path, new_name: &str) -> Result<PathBuf> {
+    check_new_name(new_name)?;
+    let current_path = &resolve_real_path(current_path)?;
+    check_renamable(current_path)?;
+    let parent_path = current_path
+        .parent()
+        .ok_or_else(|| Error::Fs("Cannot get parent directory".into()))?;
+    // On Windows the canonical path has the `\\?\` prefix, so the rename also
+    // works past MAX_PATH
+    let new_path = parent_path.join(new_name);
+
     if new_path.exists() {
         return Err(Error::Fs(format!(
             "Target path '{}' already exists",
-            new_display
+            display_path(&new_path)
         )));
     }
 
-    std::fs::rename(current_path, &new_path)
-        .map_err(|e| Error::Fs(format!("Failed to rename directory: {}", e)))?;
+    rename_or_copy(current_path, &new_path, |from, to| {
+        std::fs::rename(from, to)
+    })?;
+    crate::audit::record(
+        &new_path,
+        "rename-directory",
+        &display_path(current_path),
+        &display_path(&new_path),
+    );
 
-    // Output a machine-readable marker for the shell wrapper to detect
-    println!("GRN_DIR_CHANGE:{}:{}", current_display, new_display);
+    Ok(new_path)
+}
+
+/// Renames `from` to `to` with `rename`, falling back to copying and then removing
+/// the original when they are on different filesystems. A failed copy is cleaned
+/// up so the original stays the only copy and the rename can be retried.
+fn rename_or_copy(
+    from: &Path,
+    to: &Path,
+    rename: impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<()> {
+    match rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            debug!(
+                "'{}' and '{}' are on different filesystems; copying instead",
+                from.display(),
+                to.display()
+            );
+            if let Err(e) = copy_dir_all(from, to) {
+                let _ = std::fs::remove_dir_all(to);
+                return Err(Error::Fs(format!("Failed to copy directory: {}", e)));
+            }
+            std::fs::remove_dir_all(from).map_err(|e| {
+                Error::Fs(format!(
+                    "Copied to '{}' but failed to remove '{}': {}",
+                    display_path(to),
+                    display_path(from),
+                    e
+                ))
+            })
+        }
+        Err(e) => Err(Error::Fs(format!("Failed to rename directory: {}", e))),
+    }
+}
+
+/// Recursively copies a directory, keeping file and directory permissions and
+/// recreating symlinks rather than following them.
+pub fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let (source, target) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir_all(&source, &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&source, &target)?;
+        } else {
+            std::fs::copy(&source, &target)?;
+        }
+    }
+    std::fs::set_permissions(to, std::fs::metadata(from)?.permissions())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, target)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::fs::copy(source, target).map(|_| ())
+}
+
+/// Refuses to rename the filesystem root, the home directory or a path without a
+/// real parent, however the caller arrived at it.
+pub fn check_renamable(path: &Path) -> Result<()> {
+    let resolved = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let is_home = dirs::home_dir()
+        .map(|home| home.canonicalize().unwrap_or(home))
+        .is_some_and(|home| home == resolved);
+    let has_parent = resolved
+        .parent()
+        .is_some_and(|parent| !parent.as_os_str().is_empty() && parent != resolved);
+
+    if is_home || !has_parent {
+        return Err(Error::Fs(format!(
+            "Refusing to rename '{}': it is the filesystem root, the home directory or has no parent directory",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Rejects a new directory name that isn't a single path component, such as
+/// `../evil` or `foo/bar`, so a rename can't move the directory out of its parent.
+fn check_new_name(new_name: &str) -> Result<()> {
+    let is_plain_name = !new_name.contains(['/', '\\'])
+        && matches!(
+            Path::new(new_name).components().collect::<Vec<_>>()[..],
+            [std::path::Component::Normal(_)]
+        );
+    if !is_plain_name {
+        return Err(Error::Fs(format!(
+            "Refusing to rename to '{}': the new name must be a single directory name",
+            new_name
+        )));
+    }
+    if cfg!(windows) && is_windows_reserved_name(new_name) {
+        return Err(Error::Fs(format!(
+            "Cannot rename to '{}': it is a reserved device name on Windows",
+            new_name
+        )));
+    }
+    Ok(())
+}
 
+/// Whether Windows reserves `name` for a device (`CON`, `PRN`, `AUX`, `NUL`,
+/// `COM0`-`COM9`, `LPT0`-`LPT9`). Like Windows, this ignores case and anything
+/// after the first dot, so `con.git` is reserved too. A valid GitHub repository
+/// name such as `con` can't be a directory there.
+fn is_windows_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    let upper = stem.to_ascii_uppercase();
+    match upper.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" => true,
+        _ => {
+            (upper.starts_with("COM") || upper.starts_with("LPT"))
+                && upper.len() == 4
+                && upper.as_bytes()[3].is_ascii_digit()
+        }
+    }
+}
+
+/// A path as shown to the user, without trailing slashes.
+pub fn display_path(path: &Path) -> String {
+    path.to_string_lossy().trim_end_matches('/').to_string()
+}
+
+/// Writes a new file next to `path` through `write`, then renames it over `path`,
+/// so the file is never seen half-written. The temporary file has a unique name,
+/// so concurrent runs don't write into each other's, and is created readable
+/// only by the user, so secrets in it are never exposed.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut temp_file = tempfile::NamedTempFile::new_in(parent)?;
+    write(temp_file.as_file_mut())?;
+    temp_file.persist(path).map_err(|e| e.error)?;
     Ok(())
 }
 
@@ -55,20 +231,111 @@ pub fn set_secure_permissions(path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Resolves a file path to its canonical form, following symlinks.
-pub fn resolve_canonical_path(path: &Path) -> Result<String> {
+/// Expands a leading `~` or `~/` to the home directory. Other paths are returned
+/// unchanged.
+pub fn expand_tilde(path: &Path) -> PathBuf {
     let path_str = path.to_string_lossy();
-    let path_to_resolve = if path_str.starts_with("file://") {
-        Path::new(&path_str[7..])
+    let rest = match path_str.as_ref() {
+        "~" => "",
+        s => match s.strip_prefix("~/") {
+            Some(rest) => rest,
+            None => return path.to_path_buf(),
+        },
+    };
+
+    let home = if cfg!(unix) {
+        std::env::var_os("HOME").map(PathBuf::from)
     } else {
-        path
+        dirs::home_dir()
     };
 
+    match home {
+        Some(home) if rest.is_empty() => home,
+        Some(home) => home.join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Resolves a file path to its canonical form, expanding `~` and following symlinks.
+pub fn resolve_canonical_path(path: &Path) -> Result<String> {
+    Ok(file_url(&resolve_real_path(path)?))
+}
+
+/// Like [`resolve_canonical_path`], but returns the resolved path itself rather
+/// than a `file://` URL.
+pub fn resolve_real_path(path: &Path) -> Result<PathBuf> {
+    let path_str = path.to_string_lossy();
+    let path_to_resolve = expand_tilde(Path::new(strip_file_scheme(&path_str).as_ref()));
+
     let canonical = path_to_resolve
         .canonicalize()
         .map_err(|e| Error::Fs(format!("Failed to resolve path: {}", e)))?;
+    debug!(
+        "Resolved '{}' to canonical path '{}'",
+        path.display(),
+        canonical.display()
+    );
+
+    Ok(canonical)
+}
+
+/// Resolves a file path to an absolute `file://` URL the way
+/// [`resolve_canonical_path`] would, but lexically: symlinks aren't followed and
+/// the path doesn't have to exist.
+pub fn resolve_absolute_path(path: &Path) -> Result<String> {
+    let path_str = path.to_string_lossy();
+    let expanded = expand_tilde(Path::new(strip_file_scheme(&path_str).as_ref()));
+    let absolute = std::env::current_dir()?.join(expanded).clean();
 
-    Ok(format!("file://{}", canonical.display()))
+    Ok(file_url(&absolute))
+}
+
+/// Strips a `file://` prefix. On Windows, `file:///C:/repos` is `C:/repos` and
+/// `file://server/share` is the UNC path `//server/share`.
+fn strip_file_scheme(url: &str) -> Cow<'_, str> {
+    let Some(path) = url.strip_prefix("file://") else {
+        return Cow::Borrowed(url);
+    };
+    if !cfg!(windows) {
+        return Cow::Borrowed(path);
+    }
+
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[2] == b':' {
+        Cow::Borrowed(&path[1..])
+    } else if !path.starts_with('/') {
+        Cow::Owned(format!("//{}", path))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Formats an absolute path as a `file://` URL. On Windows, the verbatim prefix
+/// that `canonicalize` adds is dropped and separators become forward slashes.
+fn file_url(path: &Path) -> String {
+    let path = path.display().to_string();
+    if cfg!(windows) {
+        normalize_windows_file_url(&path)
+    } else {
+        format!("file://{}", path)
+    }
+}
+
+/// `C:\repos\foo.git` becomes `file:///C:/repos/foo.git`, and the UNC path
+/// `\\server\share\foo.git` becomes `file://server/share/foo.git`.
+fn normalize_windows_file_url(path: &str) -> String {
+    let path = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(path).to_string()
+    };
+    let path = path.replace('\\', "/");
+
+    match path.strip_prefix("//") {
+        Some(unc) => format!("file://{}", unc),
+        None if path.starts_with('/') => format!("file://{}", path),
+        None => format!("file:///{}", path),
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +344,35 @@ mod tests {
     use assert_fs::prelude::*;
     #[cfg(unix)]
     use std::os::unix::fs::symlink;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_refuses_to_rename_root_or_home() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let home = temp.child("home");
+        home.create_dir_all()?;
+        let original_home = std::env::var_os("HOME");
+        std::env::set_var("HOME", home.path());
+
+        let root = rename_directory(Path::new("/"), "renamed", true);
+        let home_dry_run = rename_directory(home.path(), "renamed", true);
+        let home_moved = move_directory(home.path(), "renamed");
+        match original_home {
+            Some(value) => std::env::set_var("HOME", value),
+            None => std::env::remove_var("HOME"),
+        }
+
+        for result in [root, home_dry_run, home_moved.map(|_| ())] {
+            assert!(
+                matches!(&result, Err(Error::Fs(message)) if message.starts_with("Refusing to rename")),
+                "Expected a refusal, got: {:?}",
+                result
+            );
+        }
+        assert!(home.path().exists());
+
+        Ok(())
+    }
     use std::path::Path;
 
     #[test]
@@ -137,6 +433,119 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_rename_directory_rejects_paths_as_new_name() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let parent = temp.child("parent");
+        let source = parent.child("source");
+        source.create_dir_all()?;
+
+        for new_name in ["../evil", "foo/bar", "..", "", "foo\\bar"] {
+            for result in [
+                rename_directory(source.path(), new_name, true),
+                move_directory(source.path(), new_name).map(|_| ()),
+            ] {
+                assert!(
+                    matches!(&result, Err(Error::Fs(message)) if message.starts_with("Refusing to rename to")),
+                    "Expected '{}' to be rejected, got: {:?}",
+                    new_name,
+                    result
+                );
+            }
+        }
+        assert!(source.path().exists());
+        assert!(!temp.child("evil").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_windows_reserved_name() {
+        for name in ["con", "CON", "Aux", "nul.git", "com1", "LPT9", "prn "] {
+            assert!(is_windows_reserved_name(name), "{}", name);
+        }
+        for name in ["console", "con-repo", "com10", "lpt", "my.con", "repo"] {
+            assert!(!is_windows_reserved_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_rename_directory_rejects_reserved_name() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let source = temp.child("source");
+        source.create_dir_all()?;
+
+        let result = move_directory(source.path(), "con");
+        assert!(
+            matches!(&result, Err(Error::Fs(message)) if message.contains("reserved device name")),
+            "Expected a reserved name error, got: {:?}",
+            result
+        );
+        assert!(source.path().exists());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_rename_directory_long_path() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let mut parent = temp.path().to_path_buf();
+        while parent.as_os_str().len() < 300 {
+            parent.push("a".repeat(50));
+        }
+        let source = parent.join("source");
+        std::fs::create_dir_all(&source)?;
+
+        let new_path = move_directory(&source, "renamed")?;
+        assert!(new_path.exists());
+        assert!(new_path.ends_with("renamed"));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_rename_falls_back_to_copy_across_filesystems() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child("old.git/objects/pack").create_dir_all()?;
+        temp.child("old.git/HEAD")
+            .write_str("ref: refs/heads/main\n")?;
+        temp.child("old.git/hooks/pre-push")
+            .write_str("#!/bin/sh\n")?;
+        let hook = temp.path().join("old.git/hooks/pre-push");
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+        symlink("HEAD", temp.path().join("old.git/HEAD-link"))?;
+
+        let cross_device =
+            |_: &Path, _: &Path| Err(std::io::Error::from(std::io::ErrorKind::CrossesDevices));
+        let new_path = temp.path().join("new.git");
+        rename_or_copy(&temp.path().join("old.git"), &new_path, cross_device)?;
+
+        temp.child("old.git").assert(predicates::path::missing());
+        temp.child("new.git/objects/pack")
+            .assert(predicates::path::is_dir());
+        temp.child("new.git/HEAD").assert("ref: refs/heads/main\n");
+        let hook_mode = std::fs::metadata(new_path.join("hooks/pre-push"))?
+            .permissions()
+            .mode();
+        assert_eq!(hook_mode & 0o777, 0o755);
+        assert_eq!(
+            std::fs::read_link(new_path.join("HEAD-link"))?,
+            Path::new("HEAD")
+        );
+
+        // Other errors are reported without copying anything
+        let denied =
+            |_: &Path, _: &Path| Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        let result = rename_or_copy(&new_path, &temp.path().join("other.git"), denied);
+        assert!(matches!(result, Err(Error::Fs(_))));
+        temp.child("other.git").assert(predicates::path::missing());
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_set_secure_permissions_on_unix() -> anyhow::Result<()> {
@@ -159,7 +568,7 @@ mod tests {
         real_dir.create_dir_all()?;
 
         let resolved = resolve_canonical_path(real_dir.path())?;
-        let expected = format!("file://{}", real_dir.path().canonicalize()?.display());
+        let expected = file_url(&real_dir.path().canonicalize()?);
         assert_eq!(resolved, expected);
 
         let file_url = format!("file://{}", real_dir.path().display());
@@ -177,4 +586,78 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_normalize_windows_file_url() {
+        let cases = [
+            (r"C:\repos\foo.git", "file:///C:/repos/foo.git"),
+            (r"\\?\C:\repos\foo.git", "file:///C:/repos/foo.git"),
+            (r"\\server\share\foo.git", "file://server/share/foo.git"),
+            (
+                r"\\?\UNC\server\share\foo.git",
+                "file://server/share/foo.git",
+            ),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(normalize_windows_file_url(path), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_resolve_canonical_path_drive_letter() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let repo_dir = temp.child("foo.git");
+        repo_dir.create_dir_all()?;
+
+        let resolved = resolve_canonical_path(repo_dir.path())?;
+        assert!(resolved.starts_with("file:///"), "{}", resolved);
+        assert!(
+            !resolved.contains('\\') && !resolved.contains('?'),
+            "{}",
+            resolved
+        );
+        assert!(resolved.ends_with("/foo.git"), "{}", resolved);
+        assert_eq!(resolve_canonical_path(Path::new(&resolved))?, resolved);
+        assert_eq!(crate::git::extract_repo_name_from_path(&resolved)?, "foo");
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_canonical_path_expands_tilde() -> anyhow::Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let repo_dir = temp.child("repos/upstream.git");
+        repo_dir.create_dir_all()?;
+        git2::Repository::init_bare(repo_dir.path())?;
+        let expected = format!("file://{}", repo_dir.path().canonicalize()?.display());
+
+        let original_home = std::env::var_os("HOME");
+        std::env::set_var("HOME", temp.path());
+        let results = (
+            resolve_canonical_path(Path::new("~/repos/upstream.git")),
+            resolve_canonical_path(Path::new("file://~/repos/upstream.git")),
+            resolve_canonical_path(Path::new("~")),
+        );
+        match original_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+
+        assert_eq!(results.0?, expected);
+        assert_eq!(results.1?, expected);
+        assert_eq!(
+            results.2?,
+            format!("file://{}", temp.path().canonicalize()?.display())
+        );
+        // Only a leading ~ is special
+        assert_eq!(
+            expand_tilde(Path::new("~user/repo")),
+            Path::new("~user/repo")
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,40 @@
+use crate::types::Result;
+use std::io::{BufRead, Write};
+
+/// Asks a yes/no question and reads the answer from `input`.
+/// Anything other than "y" or "yes" (case-insensitive) counts as no.
+pub fn confirm(input: &mut dyn BufRead, question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    println!();
+
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_confirm() -> anyhow::Result<()> {
+        let cases = vec![
+            ("y\n", true),
+            ("YES\n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+        ];
+
+        for (answer, expected) in cases {
+            let mut input = Cursor::new(answer);
+            assert_eq!(confirm(&mut input, "Continue?")?, expected);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::process::Output;
+
+fn run_pull_dry_run(color: &str) -> anyhow::Result<Output> {
+    let temp = assert_fs::TempDir::new()?;
+    let remote_url = common::create_bare_repo(temp.path(), "new-name.git")?;
+    let repo_dir = common::create_repo(temp.path(), "old-name", &remote_url)?;
+
+    Ok(common::command(temp.path())
+        .args(["pull", "--dry-run", "--color", color])
+        .current_dir(&repo_dir)
+        .env_remove("NO_COLOR")
+        .output()?)
+}
+
+#[test]
+fn test_color_never_emits_no_ansi_codes() -> anyhow::Result<()> {
+    let output = run_pull_dry_run("never")?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(
+        stdout.contains("Would rename directory"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        !stdout.contains('\x1b'),
+        "Unexpected ANSI codes: {:?}",
+        stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_color_always_highlights_old_and_new_values() -> anyhow::Result<()> {
+    let output = run_pull_dry_run("always")?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(
+        stdout.contains("\x1b[32m") && stdout.contains("new-name\x1b[0m"),
+        "Expected the new name in green: {:?}",
+        stdout
+    );
+
+    Ok(())
+}
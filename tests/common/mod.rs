@@ -0,0 +1,56 @@
+//! Fixtures shared by the integration tests, which run the built binary with a
+//! temporary directory as its home, so it never sees the user's config.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+pub const BINARY: &str = env!("CARGO_BIN_EXE_git-repo-name");
+
+/// Runs the binary with `home` as its home directory; its config lives in
+/// `home/.config/git-repo-name`.
+pub fn command(home: &Path) -> Command {
+    let mut command = Command::new(BINARY);
+    command
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("HOME", home);
+    command
+}
+
+/// Writes the config file the binary reads when run by [`command`], returning
+/// its path.
+pub fn write_config(home: &Path, contents: &str) -> anyhow::Result<PathBuf> {
+    let config_dir = home.join(".config/git-repo-name");
+    std::fs::create_dir_all(&config_dir)?;
+    let config_file = config_dir.join("config");
+    std::fs::write(&config_file, contents)?;
+    Ok(config_file)
+}
+
+/// Creates a bare repository at `home/name`, returning its canonical `file://` URL.
+pub fn create_bare_repo(home: &Path, name: &str) -> anyhow::Result<String> {
+    let path = home.join(name);
+    git2::Repository::init_bare(&path)?;
+    Ok(format!("file://{}", path.canonicalize()?.display()))
+}
+
+/// Creates a repository at `home/name` whose `origin` remote is `remote_url`,
+/// returning its path.
+pub fn create_repo(home: &Path, name: &str, remote_url: &str) -> anyhow::Result<PathBuf> {
+    let path = home.join(name);
+    std::fs::create_dir_all(&path)?;
+    git2::Repository::init(&path)?.remote("origin", remote_url)?;
+    Ok(path)
+}
+
+/// Fails unless the binary exited successfully, returning what it printed.
+pub fn stdout_of_success(output: Output) -> anyhow::Result<String> {
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(stdout)
+}
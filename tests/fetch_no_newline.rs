@@ -0,0 +1,17 @@
+mod common;
+
+#[test]
+fn test_fetch_no_newline_prints_only_the_name() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+    let remote_url = common::create_bare_repo(temp.path(), "upstream-repo.git")?;
+    let repo_dir = common::create_repo(temp.path(), "local-repo", &remote_url)?;
+
+    let output = common::command(temp.path())
+        .args(["fetch", "-0"])
+        .current_dir(&repo_dir)
+        .output()?;
+
+    assert_eq!(common::stdout_of_success(output)?, "upstream-repo");
+
+    Ok(())
+}
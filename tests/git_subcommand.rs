@@ -0,0 +1,45 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+fn usage_line(output: std::process::Output) -> anyhow::Result<String> {
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(output.status.success(), "stdout: {}", stdout);
+    Ok(stdout
+        .lines()
+        .find(|line| line.starts_with("Usage:"))
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[test]
+fn test_help_names_git_subcommand() -> anyhow::Result<()> {
+    let binary = Path::new(common::BINARY);
+    let path = std::env::join_paths(
+        std::iter::once(binary.parent().unwrap().to_path_buf()).chain(std::env::split_paths(
+            &std::env::var_os("PATH").unwrap_or_default(),
+        )),
+    )?;
+
+    // `git repo-name --help` would open git's man page for the command instead
+    let output = Command::new("git")
+        .args(["repo-name", "-h"])
+        .env("PATH", path)
+        .output()?;
+    assert_eq!(
+        usage_line(output)?,
+        "Usage: git repo-name [OPTIONS] <COMMAND>"
+    );
+
+    let output = Command::new(binary)
+        .arg("-h")
+        .env_remove("GIT_EXEC_PATH")
+        .output()?;
+    assert_eq!(
+        usage_line(output)?,
+        "Usage: git-repo-name [OPTIONS] <COMMAND>"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+mod common;
+
+#[test]
+fn test_login_polls_until_authorized_and_stores_token() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+    let config_file = common::write_config(
+        temp.path(),
+        "[meta]\nversion=1\n\n[github]\nclient_id=test-client\n",
+    )?;
+
+    let mut server = mockito::Server::new();
+    let device_code = server
+        .mock("POST", "/login/device/code")
+        .match_body(mockito::Matcher::UrlEncoded(
+            "client_id".into(),
+            "test-client".into(),
+        ))
+        .with_header("content-type", "application/json")
+        .with_body(
+            serde_json::json!({
+                "device_code": "device-123",
+                "user_code": "ABCD-1234",
+                "verification_uri": "https://github.com/login/device",
+                "expires_in": 900,
+                "interval": 0
+            })
+            .to_string(),
+        )
+        .create();
+    // Mocks with hits left are matched in creation order
+    let pending = server
+        .mock("POST", "/login/oauth/access_token")
+        .match_body(mockito::Matcher::UrlEncoded(
+            "device_code".into(),
+            "device-123".into(),
+        ))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"error": "authorization_pending"}"#)
+        .expect(1)
+        .create();
+    let authorized = server
+        .mock("POST", "/login/oauth/access_token")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"access_token": "gho_new-token", "token_type": "bearer"}"#)
+        .expect(1)
+        .create();
+
+    let output = common::command(temp.path())
+        .arg("login")
+        .env("GITHUB_OAUTH_BASE_URL", server.url())
+        .env_remove("GITHUB_TOKEN")
+        .output()?;
+
+    let stdout = common::stdout_of_success(output)?;
+    assert!(stdout.contains("ABCD-1234"), "stdout: {}", stdout);
+    device_code.assert();
+    pending.assert();
+    authorized.assert();
+
+    let config = std::fs::read_to_string(config_file)?;
+    assert!(config.contains("token=gho_new-token"), "config: {}", config);
+
+    Ok(())
+}
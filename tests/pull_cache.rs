@@ -0,0 +1,61 @@
+mod common;
+
+fn pull_recursive(
+    temp: &assert_fs::TempDir,
+    api_url: &str,
+    extra: &[&str],
+) -> anyhow::Result<String> {
+    let output = common::command(temp.path())
+        .arg("-C")
+        .arg(temp.path().join("src"))
+        .args(["pull", "--recursive", "--cache"])
+        .args(extra)
+        .env("GITHUB_TOKEN", "mock-token")
+        .env("GITHUB_API_BASE_URL", api_url)
+        .output()?;
+
+    common::stdout_of_success(output)
+}
+
+#[test]
+fn test_pull_recursive_cache_skips_unchanged_repos() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+    common::create_repo(temp.path(), "src/repo", "https://github.com/owner/repo.git")?;
+
+    let mut server = mockito::Server::new();
+    let get_repo = server
+        .mock("GET", "/repos/owner/repo")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"name":"repo","full_name":"owner/repo","clone_url":"https://github.com/owner/repo.git"}"#,
+        )
+        .expect(1)
+        .create();
+
+    pull_recursive(&temp, &server.url(), &[])?;
+    let cache = std::fs::read_to_string(temp.path().join(".config/git-repo-name/pull-cache.json"))?;
+    assert!(
+        cache.contains("\"remote_url\": \"https://github.com/owner/repo.git\""),
+        "cache: {}",
+        cache
+    );
+
+    // The second run trusts the cache and makes no request
+    let stdout = pull_recursive(&temp, &server.url(), &[])?;
+    assert!(stdout.contains("already up-to-date"), "stdout: {}", stdout);
+    get_repo.assert();
+
+    // --force checks the repository again
+    let recheck = server
+        .mock("GET", "/repos/owner/repo")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"name":"repo","full_name":"owner/repo","clone_url":"https://github.com/owner/repo.git"}"#,
+        )
+        .expect(1)
+        .create();
+    pull_recursive(&temp, &server.url(), &["--force"])?;
+    recheck.assert();
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use std::path::Path;
+use std::process::Output;
+
+fn run_pull_dry_run(temp: &Path, bare_name: &str, local_name: &str) -> anyhow::Result<Output> {
+    let remote_url = common::create_bare_repo(temp, bare_name)?;
+    let repo_dir = common::create_repo(temp, local_name, &remote_url)?;
+
+    Ok(common::command(temp)
+        .args(["pull", "--dry-run"])
+        .current_dir(repo_dir)
+        .output()?)
+}
+
+#[test]
+fn test_pull_dry_run_exits_with_drift_code() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+
+    let output = run_pull_dry_run(temp.path(), "new-name.git", "old-name")?;
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "Expected drift exit code, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(temp.path().join("old-name").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_pull_dry_run_exits_cleanly_when_in_sync() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+
+    let output = run_pull_dry_run(temp.path(), "same-name.git", "same-name")?;
+
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "Expected success exit code, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Ok(())
+}
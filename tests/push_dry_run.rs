@@ -0,0 +1,63 @@
+mod common;
+
+use std::path::Path;
+
+fn run_push_dry_run(temp: &Path, api_url: &str, verbose: bool) -> anyhow::Result<String> {
+    let mut command = common::command(temp);
+    command.args(["push", "--dry-run"]);
+    if verbose {
+        command.arg("-v");
+    }
+    let output = command
+        .current_dir(temp.join("new-name"))
+        .env("GITHUB_TOKEN", "mock-token")
+        .env("GITHUB_API_BASE_URL", api_url)
+        .output()?;
+
+    common::stdout_of_success(output)
+}
+
+#[test]
+fn test_verbose_push_dry_run_shows_api_request() -> anyhow::Result<()> {
+    let temp = assert_fs::TempDir::new()?;
+    common::create_repo(
+        temp.path(),
+        "new-name",
+        "https://github.com/owner/old-name.git",
+    )?;
+
+    let mut server = mockito::Server::new();
+    let _repo = server
+        .mock("GET", "/repos/owner/old-name")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"name":"old-name","full_name":"owner/old-name","clone_url":"https://github.com/owner/old-name.git"}"#,
+        )
+        .create();
+    let _free_name = server
+        .mock("GET", "/repos/owner/new-name")
+        .with_status(404)
+        .create();
+    let patch = server
+        .mock("PATCH", mockito::Matcher::Any)
+        .expect(0)
+        .create();
+
+    let api_line = format!(
+        "Would PATCH {}/repos/owner/old-name with name=new-name",
+        server.url()
+    );
+    let stdout = run_push_dry_run(temp.path(), &server.url(), true)?;
+    assert!(stdout.contains(&api_line), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("Would update GitHub repository name from 'old-name' to 'new-name'"),
+        "stdout: {}",
+        stdout
+    );
+
+    let stdout = run_push_dry_run(temp.path(), &server.url(), false)?;
+    assert!(!stdout.contains("Would PATCH"), "stdout: {}", stdout);
+    patch.assert();
+
+    Ok(())
+}